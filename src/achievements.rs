@@ -0,0 +1,232 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cleanup::Dead,
+    game::{ElapsedTime, GameOverEvent, GameState},
+    obstacles::{ObstacleKind, CORPSE_REACH},
+    physics::{CollisionEvent, ProjectileCollisionEvent},
+    player::{AttackState, Player},
+    storage,
+    ui::{Score, ScoreEvent, UiAssets},
+};
+
+const ACHIEVEMENTS_FILE: &str = "achievements.ron";
+/// Seconds a toast stays on screen
+const TOAST_TIME: f32 = 4.0;
+
+/// Goals unlocked by playing well, kept between sessions
+pub struct AchievementPlugin {
+    /// Off while watching a replay, someone else's run doesn't unlock anything
+    pub unlocks: bool,
+}
+
+impl Plugin for AchievementPlugin {
+    fn build(&self, app: &mut App) {
+        let start = SystemSet::on_enter(GameState::Playing).with_system(reset_run_stats);
+        let tick = SystemSet::new()
+            .with_run_criteria("run_tick")
+            .with_system(combo_achievement.after("score"))
+            .with_system(
+                cloud_achievement
+                    .after("collision")
+                    .before("player_collision"),
+            )
+            .with_system(corpse_achievement.after("projectiles"));
+
+        app.insert_resource(Achievements::load())
+            .init_resource::<RunStats>()
+            .add_event::<AchievementUnlocked>()
+            .add_system(spawn_toasts);
+        if self.unlocks {
+            app.add_system_set(start)
+                .add_system_set(tick)
+                .add_system(survival_achievement);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Achievement {
+    /// Reach a combo of 50
+    Combo50,
+    /// Destroy 3 clouds in one run
+    CloudBuster,
+    /// Kill a bird with a falling corpse
+    CorpseKill,
+    /// Last 2 minutes in one run
+    Survivor,
+}
+
+impl Achievement {
+    fn title(&self) -> &'static str {
+        match self {
+            Achievement::Combo50 => "Unstoppable",
+            Achievement::CloudBuster => "Cloud Buster",
+            Achievement::CorpseKill => "Bird Rain",
+            Achievement::Survivor => "Survivor",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Achievement::Combo50 => "Reach a combo of 50",
+            Achievement::CloudBuster => "Destroy 3 clouds in one run",
+            Achievement::CorpseKill => "Kill a bird with a falling corpse",
+            Achievement::Survivor => "Last 2 minutes in one run",
+        }
+    }
+}
+
+/// Sent the first time an achievement is reached
+pub struct AchievementUnlocked(pub Achievement);
+
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Achievements {
+    pub unlocked: Vec<Achievement>,
+}
+
+impl Achievements {
+    fn load() -> Self {
+        storage::load(ACHIEVEMENTS_FILE).unwrap_or_default()
+    }
+
+    /// Unlocks and saves the achievement, announces it if it's new
+    fn unlock(&mut self, achievement: Achievement, ev: &mut EventWriter<AchievementUnlocked>) {
+        if self.unlocked.contains(&achievement) {
+            return;
+        }
+        self.unlocked.push(achievement);
+        storage::save(ACHIEVEMENTS_FILE, self);
+        ev.send(AchievementUnlocked(achievement));
+    }
+}
+
+/// Progress towards the achievements that have to be reached in a single run
+#[derive(Resource, Default)]
+struct RunStats {
+    /// Clouds cut down this run
+    clouds: Vec<Entity>,
+}
+
+#[derive(Component)]
+struct Toast;
+
+fn reset_run_stats(mut stats: ResMut<RunStats>) {
+    *stats = RunStats::default();
+}
+
+fn combo_achievement(
+    mut events: EventReader<ScoreEvent>,
+    score: Res<Score>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    let scored = events.iter().any(|x| matches!(x, ScoreEvent::Add { .. }));
+    if scored && score.current_combo >= 50 {
+        achievements.unlock(Achievement::Combo50, &mut unlocked);
+    }
+}
+
+fn cloud_achievement(
+    mut events: EventReader<CollisionEvent>,
+    players: Query<&Player>,
+    mut stats: ResMut<RunStats>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    events.iter().for_each(|ev| {
+        if ev.obstacle_kind != ObstacleKind::Cloud || ev.player_state == AttackState::NotAttacking {
+            return;
+        }
+        // the barbarian passes through clouds while he's invulnerable
+        if players.get(ev.player).map_or(true, |x| x.is_invulnerable()) {
+            return;
+        }
+        if !stats.clouds.contains(&ev.obstacle) {
+            stats.clouds.push(ev.obstacle);
+        }
+    });
+    if stats.clouds.len() >= 3 {
+        achievements.unlock(Achievement::CloudBuster, &mut unlocked);
+    }
+}
+
+fn corpse_achievement(
+    mut events: EventReader<ProjectileCollisionEvent>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    let kill = events.iter().any(|ev| {
+        ev.hit_kind == ObstacleKind::Bird && ev.hit_pos.distance(ev.projectile_pos) <= CORPSE_REACH
+    });
+    if kill {
+        achievements.unlock(Achievement::CorpseKill, &mut unlocked);
+    }
+}
+
+fn survival_achievement(
+    mut events: EventReader<GameOverEvent>,
+    elapsed: Res<ElapsedTime>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    if events.iter().count() > 0 && elapsed.time >= 120.0 {
+        achievements.unlock(Achievement::Survivor, &mut unlocked);
+    }
+}
+
+fn spawn_toasts(
+    mut cmd: Commands,
+    mut events: EventReader<AchievementUnlocked>,
+    toasts: Query<(), With<Toast>>,
+    ui: Res<UiAssets>,
+) {
+    // new toasts stack under the ones still showing
+    let mut shown = toasts.iter().count();
+    events.iter().for_each(|AchievementUnlocked(achievement)| {
+        cmd.spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(20.0 + 110.0 * shown as f32),
+                        left: Val::Percent(35.0),
+                        ..default()
+                    },
+                    size: Size::new(Val::Percent(30.0), Val::Px(100.0)),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            Toast,
+            Dead { timer: TOAST_TIME },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("Achievement: {}", achievement.title()),
+                TextStyle {
+                    font: ui.font.clone(),
+                    font_size: 35.0,
+                    color: Color::GOLD,
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                achievement.description(),
+                TextStyle {
+                    font: ui.font.clone(),
+                    font_size: 25.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+        shown += 1;
+    });
+}
@@ -1,10 +1,12 @@
 use bevy::prelude::*;
 
+use crate::game::not_paused;
+
 pub struct CleanerPlugin;
 
 impl Plugin for CleanerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(clean_dead.label("cleanup"));
+        app.add_system(clean_dead.label("cleanup").with_run_criteria(not_paused));
     }
 }
 
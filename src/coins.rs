@@ -0,0 +1,256 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cleanup::Dead,
+    game::{GameState, VIEW_BOX},
+    particles::ParticleEmitter,
+    physics::{Gravity, Movement, PlayerCollider},
+    player::Player,
+    replay::Playback,
+    storage,
+};
+
+const WALLET_FILE: &str = "wallet.ron";
+const COIN_SIZE: f32 = 36.0;
+const COIN_COLOR: Color = Color::rgb(1.0, 0.8, 0.1);
+
+/// Coins dropped by birds, caught by flying into them
+pub struct CoinPlugin;
+
+impl Plugin for CoinPlugin {
+    fn build(&self, app: &mut App) {
+        let tick = SystemSet::new()
+            .with_run_criteria("run_tick")
+            .with_system(collect_coins.after("movement"));
+        let update = SystemSet::on_update(GameState::Playing).with_system(wear_cosmetic);
+        let cleanup = SystemSet::on_exit(GameState::End).with_system(cleanup_coins);
+
+        app.add_system_set(tick)
+            .add_system_set(update)
+            .add_system_set(cleanup);
+    }
+}
+
+/// Keeps the coin balance and the unlocked cosmetics between sessions
+pub struct WalletPlugin;
+
+impl Plugin for WalletPlugin {
+    fn build(&self, app: &mut App) {
+        let end = SystemSet::on_enter(GameState::End).with_system(save_wallet);
+
+        app.insert_resource(Wallet::load()).add_system_set(end);
+    }
+}
+
+/// Cosmetic the shop sells, it tints the barbarian
+pub struct ShopItem {
+    pub name: &'static str,
+    pub price: u32,
+    pub tint: Color,
+}
+
+pub const SHOP_ITEMS: [ShopItem; 5] = [
+    ShopItem {
+        name: "Classic",
+        price: 0,
+        tint: Color::WHITE,
+    },
+    ShopItem {
+        name: "Sunburnt",
+        price: 25,
+        tint: Color::rgb(1.0, 0.6, 0.5),
+    },
+    ShopItem {
+        name: "Frostbitten",
+        price: 50,
+        tint: Color::rgb(0.6, 0.8, 1.0),
+    },
+    ShopItem {
+        name: "Swamp Dweller",
+        price: 100,
+        tint: Color::rgb(0.6, 1.0, 0.5),
+    },
+    ShopItem {
+        name: "Golden",
+        price: 250,
+        tint: Color::rgb(1.0, 0.85, 0.3),
+    },
+];
+
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Wallet {
+    pub coins: u32,
+    /// Names of the bought shop items
+    pub unlocked: Vec<String>,
+    pub equipped: Option<String>,
+}
+
+impl Wallet {
+    fn load() -> Self {
+        storage::load(WALLET_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(WALLET_FILE, self);
+    }
+
+    pub fn owns(&self, item: &ShopItem) -> bool {
+        item.price == 0 || self.unlocked.iter().any(|x| x == item.name)
+    }
+
+    pub fn is_equipped(&self, item: &ShopItem) -> bool {
+        match &self.equipped {
+            Some(equipped) => equipped == item.name,
+            None => item.price == 0,
+        }
+    }
+
+    /// Buys the item if it's affordable, returns whether the player owns it now
+    pub fn buy(&mut self, item: &ShopItem) -> bool {
+        if self.owns(item) {
+            return true;
+        }
+        if self.coins < item.price {
+            return false;
+        }
+        self.coins -= item.price;
+        self.unlocked.push(item.name.to_string());
+        true
+    }
+
+    /// Tint of the equipped cosmetic
+    pub fn tint(&self) -> Color {
+        SHOP_ITEMS
+            .iter()
+            .find(|x| self.is_equipped(x))
+            .map_or(Color::WHITE, |x| x.tint)
+    }
+}
+
+#[derive(Component)]
+struct Coin;
+
+/// Coin popping out of a dead bird
+pub fn spawn_coin(cmd: &mut Commands, location: Vec3) {
+    cmd.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(COIN_SIZE)),
+                color: COIN_COLOR,
+                ..default()
+            },
+            transform: Transform::from_translation(location),
+            ..default()
+        },
+        Movement { x: -150., y: 450. },
+        Gravity::default(),
+        Coin,
+    ));
+}
+
+fn collect_coins(
+    mut cmd: Commands,
+    coins: Query<(Entity, &Transform), With<Coin>>,
+    player: Query<(&Transform, &PlayerCollider), With<Player>>,
+    mut wallet: ResMut<Wallet>,
+    playback: Option<Res<Playback>>,
+) {
+    let Ok((player, collider)) = player.get_single() else {
+        return;
+    };
+    coins.for_each(|(entity, transform)| {
+        if transform.translation.y < VIEW_BOX.min.y - COIN_SIZE {
+            cmd.entity(entity).remove::<Coin>().insert(Dead::default());
+            return;
+        }
+        let reach = (collider.collision_size + COIN_SIZE) / 2.;
+        let distance = (player.translation - transform.translation)
+            .truncate()
+            .abs();
+        if distance.x > reach.x || distance.y > reach.y {
+            return;
+        }
+        // coins in someone else's replay aren't the player's to keep
+        if playback.is_none() {
+            wallet.coins += 1;
+        }
+        cmd.entity(entity).remove::<Coin>().insert(Dead::default());
+        cmd.spawn((
+            ParticleEmitter::new(4, Duration::new(0, 500), TimerMode::Repeating)
+                .with_color(COIN_COLOR),
+            Transform::from_translation(transform.translation),
+            Dead { timer: 0.1 },
+        ));
+    });
+}
+
+/// Tints the barbarian with the equipped cosmetic when he spawns
+fn wear_cosmetic(mut player: Query<&mut Sprite, Added<Player>>, wallet: Res<Wallet>) {
+    player.for_each_mut(|mut sprite| sprite.color = wallet.tint());
+}
+
+fn save_wallet(wallet: Res<Wallet>) {
+    wallet.save();
+}
+
+fn cleanup_coins(mut cmd: Commands, coins: Query<Entity, With<Coin>>) {
+    coins.for_each(|x| {
+        cmd.entity(x).remove::<Coin>().insert(Dead::default());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str) -> &'static ShopItem {
+        SHOP_ITEMS.iter().find(|x| x.name == name).unwrap()
+    }
+
+    #[test]
+    fn buy_needs_enough_coins() {
+        let mut wallet = Wallet {
+            coins: 24,
+            ..default()
+        };
+        assert!(!wallet.buy(item("Sunburnt")));
+        assert_eq!(wallet.coins, 24);
+        assert!(!wallet.owns(item("Sunburnt")));
+        wallet.coins = 25;
+        assert!(wallet.buy(item("Sunburnt")));
+        assert_eq!(wallet.coins, 0);
+        assert!(wallet.owns(item("Sunburnt")));
+    }
+
+    #[test]
+    fn owned_items_are_not_paid_twice() {
+        let mut wallet = Wallet {
+            coins: 60,
+            ..default()
+        };
+        assert!(wallet.buy(item("Frostbitten")));
+        assert!(wallet.buy(item("Frostbitten")));
+        assert_eq!(wallet.coins, 10);
+        assert_eq!(wallet.unlocked, ["Frostbitten"]);
+        // free items are owned from the start
+        assert!(wallet.buy(item("Classic")));
+        assert_eq!(wallet.coins, 10);
+    }
+
+    #[test]
+    fn equipped_item_tints_the_barbarian() {
+        let mut wallet = Wallet::default();
+        assert!(wallet.is_equipped(item("Classic")));
+        assert_eq!(wallet.tint(), Color::WHITE);
+        wallet.coins = 250;
+        assert!(wallet.buy(item("Golden")));
+        wallet.equipped = Some(String::from("Golden"));
+        assert!(wallet.is_equipped(item("Golden")));
+        assert!(!wallet.is_equipped(item("Classic")));
+        assert_eq!(wallet.tint(), item("Golden").tint);
+    }
+}
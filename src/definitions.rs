@@ -0,0 +1,350 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    game::VIEW_BOX,
+    obstacles::{Homing, ObstacleKind},
+    powerups::PowerUpKind,
+    storage,
+};
+
+const DEFINITIONS_FILE: &str = "obstacles.ron";
+const POWER_UPS_FILE: &str = "powerups.ron";
+const SKINS_FILE: &str = "skins.ron";
+/// Definitions the game was built with, used when the file in the asset folder can't be read
+const BUILT_IN_DEFINITIONS: &str = include_str!("../assets/obstacles.ron");
+const BUILT_IN_POWER_UPS: &str = include_str!("../assets/powerups.ron");
+const BUILT_IN_SKINS: &str = include_str!("../assets/skins.ron");
+
+/// Reads a definitions file from the asset folder, falls back to the built in copy
+fn load_definitions<T: DeserializeOwned>(file: &str, built_in: &str) -> T {
+    let path = Path::new(&storage::asset_folder()).join(file);
+    match storage::load_path(&path) {
+        Some(o) => o,
+        None => {
+            println!(
+                "Error: couldn't load {}, using built in one",
+                path.display()
+            );
+            ron::from_str(built_in).expect("built in definitions are valid")
+        }
+    }
+}
+
+/// Whether the pair is a range of seconds `gen_range` and timers accept
+fn valid_interval(range: (f32, f32)) -> bool {
+    0.0 <= range.0 && range.0 <= range.1
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SoundSet {
+    pub files: Vec<String>,
+    pub volume: f32,
+}
+
+/// Tuning of one kind of obstacle
+#[derive(Deserialize, Clone)]
+pub struct ObstacleDefinition {
+    pub size: Vec2,
+    /// Animation frames, obstacles that don't animate have just one
+    pub sprites: Vec<String>,
+    pub corpse: String,
+    /// Color the sprites are tinted with
+    #[serde(default)]
+    pub tint: Option<Color>,
+    /// Size of the collider relative to the sprite
+    pub collider: Vec2,
+    /// Distance from the center that kills the barbarian, relative to the sprite width
+    pub kill_size: f32,
+    /// The kill size grows to this over the first minutes of a run
+    #[serde(default)]
+    pub kill_size_max: Option<f32>,
+    pub speed: f32,
+    /// Speed randomly added or removed on spawn
+    #[serde(default)]
+    pub speed_variance: f32,
+    /// Speed added for every point of combo
+    #[serde(default)]
+    pub combo_speed: f32,
+    /// Collider of the corpse relative to the sprite, corpses with it hit other obstacles
+    #[serde(default)]
+    pub corpse_collider: Option<Vec2>,
+    /// Steers towards the barbarian's height
+    #[serde(default)]
+    pub homing: Option<Homing>,
+    /// Hits it takes to go down, obstacles without it go down in one
+    #[serde(default)]
+    pub health: Option<i32>,
+    /// Seconds after a hit during which it can't be hit again
+    #[serde(default)]
+    pub invulnerability: f32,
+    /// Chance it drops a coin when it goes down
+    #[serde(default)]
+    pub coin_chance: f32,
+    pub hit_sounds: SoundSet,
+    pub death_sounds: SoundSet,
+}
+
+/// Entry of the spawn table
+#[derive(Deserialize, Clone)]
+pub struct SpawnEntry {
+    pub kind: ObstacleKind,
+    /// Entries in the same lane share a spawn clock, the weight picks which one spawns
+    pub lane: String,
+    pub weight: f32,
+    /// Seconds until the next spawn in the lane
+    pub interval: (f32, f32),
+    /// Interval once the run is at full difficulty
+    #[serde(default)]
+    pub hard_interval: Option<(f32, f32)>,
+    /// Band the obstacle spawns in, 0 is the bottom of the view and 1 the top
+    pub height: (f32, f32),
+    #[serde(default)]
+    pub difficulty: DifficultyCurve,
+    /// Score the run needs before the entry spawns
+    #[serde(default)]
+    pub min_score: i32,
+    /// Also spawns outside of a run, behind the menus
+    #[serde(default)]
+    pub ambient: bool,
+}
+
+/// How fast a run gets to full difficulty, whichever of the two is reached first
+#[derive(Deserialize, Clone, Default)]
+pub struct DifficultyCurve {
+    pub score: Option<i32>,
+    /// Seconds into the run
+    pub time: Option<f32>,
+}
+
+impl DifficultyCurve {
+    /// Difficulty from 0 to 1
+    pub fn progress(&self, score: i32, time: f32) -> f32 {
+        let by_score = self.score.map_or(0.0, |x| score as f32 / x as f32);
+        let by_time = self.time.map_or(0.0, |x| time / x);
+        by_score.max(by_time).clamp(0.0, 1.0)
+    }
+}
+
+/// Boss fight every few points, the spawn table pauses while the boss is alive
+#[derive(Deserialize, Clone)]
+pub struct BossEncounter {
+    pub kind: ObstacleKind,
+    /// Points between bosses
+    pub every: i32,
+    /// Where the boss stops to fight, fraction of the view from the left
+    pub hover: f32,
+}
+
+#[derive(Resource, Deserialize, Clone)]
+pub struct ObstacleDefinitions {
+    pub bird: ObstacleDefinition,
+    pub tree: ObstacleDefinition,
+    pub cloud: ObstacleDefinition,
+    pub eagle: ObstacleDefinition,
+    pub giant_bird: ObstacleDefinition,
+    pub boss: BossEncounter,
+    pub spawns: Vec<SpawnEntry>,
+}
+
+impl ObstacleDefinitions {
+    pub fn load() -> Self {
+        let defs: Self = load_definitions(DEFINITIONS_FILE, BUILT_IN_DEFINITIONS);
+        if let Err(e) = defs.validate() {
+            println!("Error: {DEFINITIONS_FILE}: {e}, using built in one");
+            return ron::from_str(BUILT_IN_DEFINITIONS).expect("built in definitions are valid");
+        }
+        defs
+    }
+
+    /// Catches values that would crash the game in the middle of a run
+    fn validate(&self) -> Result<(), String> {
+        let kinds = [
+            ObstacleKind::Bird,
+            ObstacleKind::Tree,
+            ObstacleKind::Cloud,
+            ObstacleKind::Eagle,
+            ObstacleKind::GiantBird,
+        ];
+        for kind in kinds {
+            let def = self.get(&kind);
+            if def.sprites.is_empty() {
+                return Err(format!("{kind:?} has no sprites"));
+            }
+            if def.size.min_element() <= 0.0 || def.collider.min_element() <= 0.0 {
+                return Err(format!("{kind:?} needs a positive size and collider"));
+            }
+            // sprites are clamped inside the view when they spawn
+            if def.size.y > VIEW_BOX.height() {
+                return Err(format!("{kind:?} is taller than the view"));
+            }
+        }
+        if self.boss.every <= 0 {
+            return Err(String::from("boss needs to come every few points"));
+        }
+        for entry in self.spawns.iter() {
+            if !valid_interval(entry.interval) {
+                return Err(format!("{:?} has an invalid interval", entry.kind));
+            }
+            if entry.hard_interval.is_some_and(|x| !valid_interval(x)) {
+                return Err(format!("{:?} has an invalid hard interval", entry.kind));
+            }
+            if entry.height.0 > entry.height.1 {
+                return Err(format!("{:?} has an invalid height", entry.kind));
+            }
+            if entry.weight < 0.0 {
+                return Err(format!("{:?} has a negative weight", entry.kind));
+            }
+            // a lane that can never pick an entry never spawns again
+            let lane_weight: f32 = self
+                .spawns
+                .iter()
+                .filter(|x| x.lane == entry.lane)
+                .map(|x| x.weight)
+                .sum();
+            if lane_weight <= 0.0 {
+                return Err(format!("lane {} has no weight", entry.lane));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, kind: &ObstacleKind) -> &ObstacleDefinition {
+        match kind {
+            ObstacleKind::Bird => &self.bird,
+            ObstacleKind::Tree => &self.tree,
+            ObstacleKind::Cloud => &self.cloud,
+            ObstacleKind::Eagle => &self.eagle,
+            ObstacleKind::GiantBird => &self.giant_bird,
+        }
+    }
+}
+
+/// Pickup that can show up during a run
+#[derive(Deserialize, Clone)]
+pub struct PowerUpDefinition {
+    pub kind: PowerUpKind,
+    pub weight: f32,
+    /// Seconds the effect lasts
+    pub duration: f32,
+    pub color: Color,
+    /// Short text drawn on the pickup and shown in the HUD
+    pub label: String,
+}
+
+#[derive(Resource, Deserialize, Clone)]
+pub struct PowerUpDefinitions {
+    /// Seconds between pickups
+    pub interval: (f32, f32),
+    /// Band pickups spawn in, 0 is the bottom of the view and 1 the top
+    pub height: (f32, f32),
+    pub size: Vec2,
+    pub speed: f32,
+    /// Score the run needs before pickups show up
+    pub min_score: i32,
+    pub power_ups: Vec<PowerUpDefinition>,
+    /// Points are multiplied by this while the multiplier lasts
+    pub score_multiplier: i32,
+    /// Speed of the simulation during slow motion
+    pub slow_motion: f32,
+    /// How much bigger the barbarian's reach gets with the big axe
+    pub big_axe: f32,
+}
+
+impl PowerUpDefinitions {
+    pub fn load() -> Self {
+        let defs: Self = load_definitions(POWER_UPS_FILE, BUILT_IN_POWER_UPS);
+        if let Err(e) = defs.validate() {
+            println!("Error: {POWER_UPS_FILE}: {e}, using built in one");
+            return ron::from_str(BUILT_IN_POWER_UPS).expect("built in definitions are valid");
+        }
+        defs
+    }
+
+    /// Catches values that would crash the game in the middle of a run
+    fn validate(&self) -> Result<(), String> {
+        if !valid_interval(self.interval) {
+            return Err(String::from("invalid interval"));
+        }
+        if self.height.0 > self.height.1 {
+            return Err(String::from("invalid height"));
+        }
+        // pickups are clamped inside the view when they spawn
+        if self.size.y > VIEW_BOX.height() {
+            return Err(String::from("pickups are taller than the view"));
+        }
+        if let Some(x) = self.power_ups.iter().find(|x| x.weight < 0.0) {
+            return Err(format!("{:?} has a negative weight", x.kind));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, kind: &PowerUpKind) -> Option<&PowerUpDefinition> {
+        self.power_ups.iter().find(|x| x.kind == *kind)
+    }
+}
+
+/// Sprites and sounds of one barbarian skin
+#[derive(Deserialize, Clone)]
+pub struct SkinDefinition {
+    pub name: String,
+    pub falling: String,
+    pub swing: String,
+    pub chop: String,
+    pub dead: String,
+    pub axe: String,
+    #[serde(default)]
+    pub attack_sounds: Option<SoundSet>,
+    #[serde(default)]
+    pub death_sounds: Option<SoundSet>,
+}
+
+#[derive(Resource, Deserialize, Clone)]
+pub struct SkinDefinitions {
+    pub skins: Vec<SkinDefinition>,
+}
+
+impl SkinDefinitions {
+    pub fn load() -> Self {
+        let skins: Self = load_definitions(SKINS_FILE, BUILT_IN_SKINS);
+        if skins.skins.is_empty() {
+            println!("Error: {SKINS_FILE} has no skins, using built in ones");
+            return ron::from_str(BUILT_IN_SKINS).expect("built in definitions are valid");
+        }
+        skins
+    }
+
+    /// Skin with the name, the first one when there's no such skin
+    pub fn get(&self, name: Option<&str>) -> &SkinDefinition {
+        self.skins
+            .iter()
+            .find(|x| Some(x.name.as_str()) == name)
+            .unwrap_or(&self.skins[0])
+    }
+
+    /// Skin some steps before or after the named one, wrapping around
+    pub fn step(&self, name: Option<&str>, steps: i32) -> &SkinDefinition {
+        let current = self
+            .skins
+            .iter()
+            .position(|x| Some(x.name.as_str()) == name)
+            .unwrap_or(0);
+        let index = (current as i32 + steps).rem_euclid(self.skins.len() as i32);
+        &self.skins[index as usize]
+    }
+
+    /// Sounds of the skin, the first skin's when it has none of its own
+    pub fn attack_sounds<'a>(&'a self, skin: &'a SkinDefinition) -> Option<&'a SoundSet> {
+        skin.attack_sounds
+            .as_ref()
+            .or(self.skins[0].attack_sounds.as_ref())
+    }
+
+    pub fn death_sounds<'a>(&'a self, skin: &'a SkinDefinition) -> Option<&'a SoundSet> {
+        skin.death_sounds
+            .as_ref()
+            .or(self.skins[0].death_sounds.as_ref())
+    }
+}
@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::GameState,
+    player::PLAYER_JUMP_STRENGTH,
+    replay::{Playback, RecordedInputs},
+    settings::Settings,
+};
+
+/// Picks the difficulty preset every run is played with
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        let start = SystemSet::on_enter(GameState::Playing)
+            .with_system(select_difficulty.label("difficulty"));
+
+        app.init_resource::<Difficulty>().add_system_set(start);
+    }
+}
+
+/// Tuning preset of a run, chosen in the main menu
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    /// Preset some steps before or after this one, wrapping around
+    pub fn step(&self, steps: i32) -> Self {
+        let current = Self::ALL.iter().position(|x| x == self).unwrap_or(0) as i32;
+        Self::ALL[(current + steps).rem_euclid(Self::ALL.len() as i32) as usize]
+    }
+
+    /// Scales how close an obstacle has to get to kill the barbarian
+    pub fn kill_size(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.7,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.2,
+        }
+    }
+
+    /// Scales the time between spawns in every lane
+    pub fn spawn_interval(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.4,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.8,
+        }
+    }
+
+    /// Scales the speed things fall at
+    pub fn gravity(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.8,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.15,
+        }
+    }
+
+    pub fn jump_strength(&self) -> f32 {
+        let scale = match self {
+            Difficulty::Easy => 0.9,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.1,
+        };
+        PLAYER_JUMP_STRENGTH * scale
+    }
+}
+
+/// A replay is played back on the difficulty it was recorded on
+fn select_difficulty(
+    mut difficulty: ResMut<Difficulty>,
+    settings: Res<Settings>,
+    playback: Option<Res<Playback>>,
+    mut recorded: ResMut<RecordedInputs>,
+) {
+    *difficulty = match playback {
+        Some(playback) => playback.difficulty(),
+        None => settings.difficulty,
+    };
+    recorded.difficulty = *difficulty;
+}
@@ -1,9 +1,15 @@
 use bevy::{
-    core_pipeline::clear_color::ClearColorConfig, prelude::*, render::camera::ScalingMode,
-    sprite::Anchor,
+    core_pipeline::clear_color::ClearColorConfig, ecs::schedule::ShouldRun, prelude::*,
+    render::camera::ScalingMode, sprite::Anchor, window::WindowFocused,
 };
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-use crate::ui::{Score, ScoreEvent};
+use crate::{
+    input::Action,
+    modes::GameMode,
+    physics::SIMULATION_STEP,
+    ui::{Score, ScoreEvent},
+};
 
 const SKY_COLOR: Color = Color::Hsla {
     hue: 200.0,
@@ -24,28 +30,43 @@ pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        let play_start = SystemSet::on_enter(GameState::Playing).with_system(reset_elapsed_time);
-        let play_update = SystemSet::on_update(GameState::Playing)
-            .with_system(advance_elapsed_time)
-            .with_system(game_over.label("game_over").after("collision"));
+        let play_start = SystemSet::on_enter(GameState::Playing)
+            .with_system(reset_elapsed_time)
+            .with_system(reseed_rng.label("reseed"));
+        let play_update = SystemSet::on_update(GameState::Playing).with_system(pause_game);
+        let play_tick = SystemSet::new()
+            .with_run_criteria(RunCriteria::pipe("simulation_tick", run_tick).label("run_tick"))
+            .with_system(game_over.label("game_over").after("collision"))
+            .with_system(advance_elapsed_time.after("game_over"));
+
+        let pause_update = SystemSet::on_update(GameState::Paused).with_system(resume_game);
 
         let menu_update =
             SystemSet::on_update(GameState::MainMenu).with_system(start_game_shortcut);
 
         let end_update = SystemSet::on_update(GameState::End).with_system(start_game_shortcut);
 
+        let world_update = SystemSet::new()
+            .with_run_criteria(not_paused)
+            .with_system(fade_out)
+            .with_system(screen_shake)
+            .with_system(side_scroll);
+
         app.add_state(GameState::MainMenu)
             .add_event::<GameOverEvent>()
-            .insert_resource(ScreenShake { shake: 0.0 })
-            .insert_resource(ElapsedTime { time: 0.0 })
+            .insert_resource(ScreenShake {
+                shake: 0.0,
+                intensity: 1.0,
+            })
+            .insert_resource(ElapsedTime::default())
             .add_startup_system(make_camera)
             .add_startup_system(make_background)
-            .add_system(fade_out)
-            .add_system(screen_shake)
-            .add_system(side_scroll)
+            .add_system_set(world_update)
             .add_system_set(menu_update)
             .add_system_set(play_start)
             .add_system_set(play_update)
+            .add_system_set(play_tick)
+            .add_system_set(pause_update)
             .add_system_set(end_update);
     }
 }
@@ -56,13 +77,44 @@ pub struct GameOverEvent;
 #[derive(PartialEq, Hash, Debug, Eq, Clone)]
 pub enum GameState {
     Playing,
+    /// Pushed on top of Playing, the simulation is frozen until it is popped
+    Paused,
     End,
     MainMenu,
+    Settings,
+    Shop,
+}
+
+/// Run criteria piped from the simulation tick, passes the ticks on only while a run is live.
+/// Gameplay that decides the outcome of a run joins it through the "run_tick" label.
+fn run_tick(
+    In(tick): In<ShouldRun>,
+    state: Res<State<GameState>>,
+    elapsed: Res<ElapsedTime>,
+) -> ShouldRun {
+    let live = *state.current() == GameState::Playing && !elapsed.finished;
+    match tick {
+        ShouldRun::Yes if live => ShouldRun::Yes,
+        ShouldRun::YesAndCheckAgain if live => ShouldRun::YesAndCheckAgain,
+        // the state may still change to Playing later this frame
+        ShouldRun::YesAndCheckAgain | ShouldRun::NoAndCheckAgain => ShouldRun::NoAndCheckAgain,
+        ShouldRun::Yes | ShouldRun::No => ShouldRun::No,
+    }
+}
+
+/// Run criteria for systems that simulate the game world and need to freeze while paused
+pub fn not_paused(state: Res<State<GameState>>) -> ShouldRun {
+    match state.current() {
+        GameState::Paused => ShouldRun::No,
+        _ => ShouldRun::Yes,
+    }
 }
 
 #[derive(Resource)]
-struct ScreenShake {
+pub struct ScreenShake {
     shake: f32,
+    /// How far the camera rotates when shaking, set from the settings
+    pub intensity: f32,
 }
 
 #[derive(Component)]
@@ -75,9 +127,42 @@ struct Scroll {
     speed: f32,
 }
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct ElapsedTime {
     pub time: f32,
+    /// Simulation ticks since the run started
+    pub ticks: u32,
+    /// Set on the tick the run ended, the run stops ticking from there
+    pub finished: bool,
+}
+
+/// Source of all randomness in the game.
+/// It's reseeded at the start of every run so a run can be reproduced from its seed.
+#[derive(Resource)]
+pub struct GameRng {
+    /// Seed requested by the player, when missing every run gets a new random seed
+    fixed_seed: Option<u64>,
+    /// Seed of the current run
+    pub seed: u64,
+    /// Drives everything that can affect the outcome of a run
+    pub gameplay: StdRng,
+    /// Drives effects like particles and sounds, kept separate so they can't shift the gameplay rolls
+    pub cosmetic: StdRng,
+}
+
+impl GameRng {
+    pub fn new(fixed_seed: Option<u64>) -> Self {
+        let seed = fixed_seed.unwrap_or_else(rand::random);
+        Self {
+            fixed_seed,
+            seed,
+            gameplay: StdRng::seed_from_u64(seed),
+            cosmetic: StdRng::seed_from_u64(seed.wrapping_add(1)),
+        }
+    }
+    pub fn reseed(&mut self) {
+        *self = Self::new(self.fixed_seed);
+    }
 }
 
 fn make_camera(mut cmd: Commands) {
@@ -105,17 +190,18 @@ fn screen_shake(
     mut camera: Query<&mut Transform, With<Camera2d>>,
     mut event: EventReader<ScoreEvent>,
     mut shake: ResMut<ScreenShake>,
+    mut rng: ResMut<GameRng>,
     score: Res<Score>,
     time: Res<Time>,
 ) {
     event.iter().for_each(|ev| {
         shake.shake = match ev {
-            ScoreEvent::Add => 1.0,
+            ScoreEvent::Add { .. } => 1.0,
             ScoreEvent::ResetCombo => 0.0,
         };
     });
     // shaking describes the angle the camera is rotated each frame on shake
-    let shaking = 0.05 * (rand::random::<f32>() * 2.0 - 1.0);
+    let shaking = 0.05 * shake.intensity * (rng.cosmetic.gen::<f32>() * 2.0 - 1.0);
     // scale describes how fast the camera rotates towards the angle
     let scale = time.delta_seconds() * score.current_combo.min(100) as f32;
 
@@ -133,10 +219,14 @@ fn screen_shake(
 }
 
 fn reset_elapsed_time(mut elapsed: ResMut<ElapsedTime>) {
-    elapsed.time = 0.0;
+    *elapsed = ElapsedTime::default();
 }
-fn advance_elapsed_time(mut elapsed: ResMut<ElapsedTime>, time: Res<Time>) {
-    elapsed.time += time.delta_seconds();
+fn reseed_rng(mut rng: ResMut<GameRng>) {
+    rng.reseed();
+}
+fn advance_elapsed_time(mut elapsed: ResMut<ElapsedTime>) {
+    elapsed.time += SIMULATION_STEP;
+    elapsed.ticks += 1;
 }
 
 fn make_background(mut cmd: Commands, asset_server: Res<AssetServer>) {
@@ -267,7 +357,7 @@ fn make_background(mut cmd: Commands, asset_server: Res<AssetServer>) {
         transform: Transform::from_translation(Vec3 {
             x: VIEW_BOX.min.x,
             y: VIEW_BOX.min.y,
-            z: -5.0
+            z: -5.0,
         }),
         ..default()
     });
@@ -284,8 +374,16 @@ fn side_scroll(mut background: Query<(&mut Transform, &Scroll)>, time: Res<Time>
     })
 }
 
-fn game_over(go: EventReader<GameOverEvent>, mut end: ResMut<State<GameState>>) {
-    if go.is_empty() == false {
+/// Ends the run on a game over or once the mode's time runs out
+fn game_over(
+    go: EventReader<GameOverEvent>,
+    mut end: ResMut<State<GameState>>,
+    mut elapsed: ResMut<ElapsedTime>,
+    mode: Res<GameMode>,
+) {
+    let time_up = mode.time_limit().is_some_and(|x| elapsed.time >= x);
+    if !go.is_empty() || time_up {
+        elapsed.finished = true;
         if let Err(e) = end.set(GameState::End) {
             println!("Error: {e}");
         }
@@ -293,14 +391,39 @@ fn game_over(go: EventReader<GameOverEvent>, mut end: ResMut<State<GameState>>)
     }
 }
 
-fn start_game_shortcut(input: Res<Input<KeyCode>>, mut state: ResMut<State<GameState>>) {
-    if input.just_pressed(KeyCode::Return) {
+fn start_game_shortcut(input: Res<Input<Action>>, mut state: ResMut<State<GameState>>) {
+    if input.just_pressed(Action::Confirm) {
         if let Err(e) = state.set(GameState::Playing) {
             println!("Error: {e}");
         }
     }
 }
 
+fn pause_game(
+    mut input: ResMut<Input<Action>>,
+    mut focus: EventReader<WindowFocused>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let lost_focus = focus.iter().any(|x| !x.focused);
+    // clearing the action so the pause state doesn't see it and resume in the same frame
+    if input.clear_just_pressed(Action::Pause) || lost_focus {
+        if let Err(e) = state.push(GameState::Paused) {
+            println!("Error: {e}");
+        }
+    }
+}
+
+fn resume_game(mut input: ResMut<Input<Action>>, mut state: ResMut<State<GameState>>) {
+    // the pause and back actions can share a button, both are cleared so it can't pause again
+    let pause = input.clear_just_pressed(Action::Pause);
+    let back = input.clear_just_pressed(Action::Back);
+    if pause || back {
+        if let Err(e) = state.pop() {
+            println!("Error: {e}");
+        }
+    }
+}
+
 fn fade_out(mut fade: Query<(&mut Sprite, &FadeOut)>, time: Res<Time>) {
     fade.for_each_mut(|(mut sprite, fade)| {
         let a = sprite.color.a();
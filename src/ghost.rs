@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+
+use crate::{
+    cleanup::Dead,
+    difficulty::Difficulty,
+    game::{ElapsedTime, FadeOut, GameState},
+    modes::GameMode,
+    physics::{Gravity, Movement},
+    player::{player_start, AttackState, PlayerAssets, PLAYER_SIZE_X, PLAYER_SIZE_Y},
+    replay::Replay,
+};
+
+const GHOST_ALPHA: f32 = 0.35;
+
+/// Races the player against a translucent barbarian replaying their best run
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        let start = SystemSet::on_enter(GameState::Playing)
+            .with_system(spawn_ghost.after("difficulty").after("mode"));
+        let update = SystemSet::on_update(GameState::Playing).with_system(animate_ghost);
+        let tick = SystemSet::new()
+            .with_run_criteria("run_tick")
+            .with_system(ghost_jump.after("gravity").before("movement"))
+            .with_system(ghost_attack_state.after("movement"));
+        let end = SystemSet::on_enter(GameState::End).with_system(ghost_vanish);
+
+        app.add_system_set(start)
+            .add_system_set(update)
+            .add_system_set(tick)
+            .add_system_set(end);
+    }
+}
+
+/// Barbarian following the inputs of a replay, it has no collider so the world can't see it
+#[derive(Component)]
+struct Ghost {
+    replay: Replay,
+    next: usize,
+    attack_state: AttackState,
+}
+
+/// The ghost races the best run of the same difficulty and mode
+fn spawn_ghost(mut cmd: Commands, difficulty: Res<Difficulty>, mode: Res<GameMode>) {
+    let Some(replay) = Replay::best(*difficulty, *mode) else {
+        return;
+    };
+    cmd.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2 {
+                    x: PLAYER_SIZE_X,
+                    y: PLAYER_SIZE_Y,
+                }),
+                color: Color::rgba(1.0, 1.0, 1.0, GHOST_ALPHA),
+                ..default()
+            },
+            // drawn behind the player
+            transform: Transform::from_translation(player_start() - Vec3::Z * 0.1),
+            ..default()
+        },
+        Movement {
+            y: difficulty.jump_strength(),
+            ..default()
+        },
+        Gravity::default(),
+        Ghost {
+            replay,
+            next: 0,
+            attack_state: AttackState::default(),
+        },
+    ));
+}
+
+fn ghost_jump(
+    mut cmd: Commands,
+    mut ghost: Query<(Entity, &mut Ghost, &mut Movement)>,
+    elapsed: Res<ElapsedTime>,
+) {
+    ghost.for_each_mut(|(entity, mut ghost, mut movement)| {
+        if elapsed.ticks >= ghost.replay.ticks {
+            // the best run ended here
+            cmd.entity(entity).remove::<Ghost>();
+            vanish(&mut cmd, entity);
+            return;
+        }
+        let next = ghost.next;
+        if ghost.replay.jumps.get(next) == Some(&elapsed.ticks) {
+            movement.y = ghost.replay.difficulty.jump_strength();
+            ghost.next += 1;
+        }
+    });
+}
+
+fn ghost_attack_state(mut ghost: Query<(&mut Ghost, &Movement)>) {
+    ghost.for_each_mut(|(mut ghost, movement)| {
+        let jump_strength = ghost.replay.difficulty.jump_strength();
+        ghost.attack_state = AttackState::from_velocity(movement.y, jump_strength);
+    });
+}
+
+fn animate_ghost(mut cmd: Commands, ghost: Query<(Entity, &Ghost)>, assets: Res<PlayerAssets>) {
+    ghost.for_each(|(entity, ghost)| {
+        cmd.entity(entity)
+            .insert(assets.sprite(&ghost.attack_state));
+    });
+}
+
+fn ghost_vanish(mut cmd: Commands, ghost: Query<Entity, With<Ghost>>) {
+    ghost.for_each(|entity| vanish(&mut cmd, entity));
+}
+
+fn vanish(cmd: &mut Commands, entity: Entity) {
+    cmd.entity(entity)
+        .insert(FadeOut { speed: 1.0 })
+        .insert(Dead { timer: 0.5 });
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use bevy::{app::AppExit, prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    game::{ElapsedTime, GameRng, GameState},
+    ui::Score,
+};
+
+/// Length of a single simulated frame, rounded up from the simulation step so every frame runs one tick
+const FRAME_STEP: Duration = Duration::from_nanos(16_666_668);
+
+/// Drives the game without a window, renderer or audio.
+/// Time advances by a fixed step each frame, runs restart as soon as they end
+/// and the app exits once the requested amount of frames was simulated.
+pub struct HeadlessPlugin {
+    pub frames: u32,
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        let menu = SystemSet::on_update(GameState::MainMenu).with_system(start_run);
+        let end = SystemSet::on_enter(GameState::End).with_system(report_run);
+        let restart = SystemSet::on_update(GameState::End).with_system(start_run);
+
+        app.insert_resource(Simulation {
+            frame: 0,
+            frames: self.frames,
+            runs: 0,
+        })
+        .add_system_set(menu)
+        .add_system_set(end)
+        .add_system_set(restart)
+        .add_system_to_stage(CoreStage::Last, step_frame);
+    }
+}
+
+#[derive(Resource)]
+struct Simulation {
+    frame: u32,
+    frames: u32,
+    runs: u32,
+}
+
+fn start_run(mut state: ResMut<State<GameState>>) {
+    if let Err(e) = state.set(GameState::Playing) {
+        println!("Error: {e}");
+    }
+}
+
+fn report_run(
+    mut simulation: ResMut<Simulation>,
+    score: Res<Score>,
+    elapsed: Res<ElapsedTime>,
+    rng: Res<GameRng>,
+) {
+    simulation.runs += 1;
+    println!(
+        "Run {}: seed {}, score {}, max combo {}, time {:.2}s",
+        simulation.runs, rng.seed, score.score, score.max_combo, elapsed.time
+    );
+}
+
+fn step_frame(
+    mut simulation: ResMut<Simulation>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    time: Res<Time>,
+    mut exit: EventWriter<AppExit>,
+) {
+    simulation.frame += 1;
+    if simulation.frame >= simulation.frames {
+        println!(
+            "Simulated {} frames over {} finished runs",
+            simulation.frame, simulation.runs
+        );
+        exit.send(AppExit);
+        return;
+    }
+    let last = time.last_update().unwrap_or_else(|| time.startup());
+    *strategy = TimeUpdateStrategy::ManualInstant(last + FRAME_STEP);
+}
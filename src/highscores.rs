@@ -0,0 +1,212 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    difficulty::Difficulty, game::ElapsedTime, game::GameState, modes::GameMode, storage, ui::Score,
+};
+
+const HIGH_SCORE_FILE: &str = "highscores.ron";
+/// Entries kept for each difficulty and mode
+const HIGH_SCORE_COUNT: usize = 10;
+
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        let end = SystemSet::on_enter(GameState::End)
+            .with_system(record_high_score.label("record_score"));
+
+        app.insert_resource(HighScores::load()).add_system_set(end);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScoreEntry {
+    pub score: i32,
+    pub max_combo: i32,
+    /// Length of the run in seconds
+    pub time: f32,
+    /// Seconds since unix epoch when the run ended
+    pub timestamp: u64,
+    /// Every difficulty has its own table, runs from before there were presets were on Normal
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Every mode has its own table too, runs from before there were modes were Endless
+    #[serde(default)]
+    pub mode: GameMode,
+}
+
+impl ScoreEntry {
+    fn in_table(&self, difficulty: Difficulty, mode: GameMode) -> bool {
+        self.difficulty == difficulty && self.mode == mode
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct HighScores {
+    pub entries: Vec<ScoreEntry>,
+    /// The run that just finished and its place in the table, if it made it in
+    #[serde(skip)]
+    pub latest: Option<(ScoreEntry, Option<usize>)>,
+}
+
+impl HighScores {
+    fn load() -> Self {
+        storage::load(HIGH_SCORE_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        storage::save(HIGH_SCORE_FILE, self);
+    }
+
+    /// Entries of the difficulty and mode's table, best first
+    pub fn table(
+        &self,
+        difficulty: Difficulty,
+        mode: GameMode,
+    ) -> impl Iterator<Item = &ScoreEntry> {
+        self.entries
+            .iter()
+            .filter(move |x| x.in_table(difficulty, mode))
+    }
+
+    /// Inserts the entry in its place in its table and returns the place, or None if it didn't make the cut
+    pub fn insert(&mut self, entry: ScoreEntry) -> Option<usize> {
+        let (difficulty, mode) = (entry.difficulty, entry.mode);
+        let table: Vec<usize> = (0..self.entries.len())
+            .filter(|x| self.entries[*x].in_table(difficulty, mode))
+            .collect();
+        let place = table
+            .iter()
+            .position(|x| {
+                let other = &self.entries[*x];
+                (entry.score, entry.max_combo) > (other.score, other.max_combo)
+            })
+            .unwrap_or(table.len());
+        if place >= HIGH_SCORE_COUNT {
+            return None;
+        }
+        let index = table.get(place).copied().unwrap_or(self.entries.len());
+        self.entries.insert(index, entry);
+        // the entry pushed out of the table
+        let last = (0..self.entries.len())
+            .filter(|x| self.entries[*x].in_table(difficulty, mode))
+            .nth(HIGH_SCORE_COUNT);
+        if let Some(last) = last {
+            self.entries.remove(last);
+        }
+        Some(place)
+    }
+}
+
+fn record_high_score(
+    mut scores: ResMut<HighScores>,
+    score: Res<Score>,
+    elapsed: Res<ElapsedTime>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default();
+    let entry = ScoreEntry {
+        score: score.score,
+        max_combo: score.max_combo,
+        time: elapsed.time,
+        timestamp,
+        difficulty: *difficulty,
+        mode: *mode,
+    };
+    let place = scores.insert(entry.clone());
+    scores.latest = Some((entry, place));
+    scores.save();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: i32, difficulty: Difficulty, mode: GameMode) -> ScoreEntry {
+        ScoreEntry {
+            score,
+            max_combo: 0,
+            time: 0.0,
+            timestamp: 0,
+            difficulty,
+            mode,
+        }
+    }
+
+    fn scores(table: &HighScores, difficulty: Difficulty, mode: GameMode) -> Vec<i32> {
+        table.table(difficulty, mode).map(|x| x.score).collect()
+    }
+
+    #[test]
+    fn insert_keeps_best_first() {
+        let mut table = HighScores::default();
+        let normal = (Difficulty::Normal, GameMode::Endless);
+        assert_eq!(table.insert(entry(5, normal.0, normal.1)), Some(0));
+        assert_eq!(table.insert(entry(9, normal.0, normal.1)), Some(0));
+        assert_eq!(table.insert(entry(7, normal.0, normal.1)), Some(1));
+        assert_eq!(scores(&table, normal.0, normal.1), [9, 7, 5]);
+    }
+
+    #[test]
+    fn combo_breaks_ties() {
+        let mut table = HighScores::default();
+        table.insert(entry(5, Difficulty::Normal, GameMode::Endless));
+        let better = ScoreEntry {
+            max_combo: 3,
+            ..entry(5, Difficulty::Normal, GameMode::Endless)
+        };
+        assert_eq!(table.insert(better), Some(0));
+    }
+
+    #[test]
+    fn insert_truncates_each_table() {
+        let mut table = HighScores::default();
+        for score in 0..HIGH_SCORE_COUNT as i32 {
+            table.insert(entry(score + 10, Difficulty::Normal, GameMode::Endless));
+        }
+        assert_eq!(
+            table.insert(entry(1, Difficulty::Normal, GameMode::Endless)),
+            None
+        );
+        assert_eq!(
+            table.insert(entry(100, Difficulty::Normal, GameMode::Endless)),
+            Some(0)
+        );
+        let normal = scores(&table, Difficulty::Normal, GameMode::Endless);
+        assert_eq!(normal.len(), HIGH_SCORE_COUNT);
+        assert_eq!(normal[0], 100);
+        // the lowest score was pushed out
+        assert!(!normal.contains(&10));
+    }
+
+    #[test]
+    fn tables_are_separate() {
+        let mut table = HighScores::default();
+        for score in 0..HIGH_SCORE_COUNT as i32 {
+            table.insert(entry(score + 10, Difficulty::Normal, GameMode::Endless));
+        }
+        // a full table doesn't keep low scores out of the others
+        assert_eq!(
+            table.insert(entry(1, Difficulty::Hard, GameMode::Endless)),
+            Some(0)
+        );
+        assert_eq!(
+            table.insert(entry(2, Difficulty::Normal, GameMode::Zen)),
+            Some(0)
+        );
+        assert_eq!(scores(&table, Difficulty::Hard, GameMode::Endless), [1]);
+        assert_eq!(scores(&table, Difficulty::Normal, GameMode::Zen), [2]);
+        assert_eq!(
+            scores(&table, Difficulty::Normal, GameMode::Endless).len(),
+            HIGH_SCORE_COUNT
+        );
+        assert_eq!(scores(&table, Difficulty::Easy, GameMode::Endless).len(), 0);
+    }
+}
@@ -0,0 +1,188 @@
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::SystemParam, input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const BINDINGS_FILE: &str = "bindings.ron";
+
+/// Translates keyboard, mouse and gamepad buttons into game actions.
+/// Systems read the actions from `Input<Action>` instead of the devices.
+pub struct ActionInputPlugin;
+
+impl Plugin for ActionInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputBindings::load())
+            .init_resource::<Input<Action>>()
+            .add_system_to_stage(CoreStage::PreUpdate, update_actions.after(InputSystem));
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    /// Swing the axe and push the barbarian upwards
+    Jump,
+    /// Start a run from the menus
+    Confirm,
+    Pause,
+    /// Leave the current screen
+    Back,
+}
+
+impl Action {
+    pub const ALL: [Action; 4] = [Action::Jump, Action::Confirm, Action::Pause, Action::Back];
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Button on any connected gamepad
+    Gamepad(GamepadButtonType),
+}
+
+/// Buttons bound to each action, kept in a file in the data folder
+#[derive(Resource, Serialize, Deserialize, Clone)]
+pub struct InputBindings {
+    pub jump: Vec<Binding>,
+    pub confirm: Vec<Binding>,
+    pub pause: Vec<Binding>,
+    pub back: Vec<Binding>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            jump: vec![
+                Binding::Key(KeyCode::Space),
+                Binding::Mouse(MouseButton::Left),
+                Binding::Gamepad(GamepadButtonType::South),
+            ],
+            confirm: vec![
+                Binding::Key(KeyCode::Return),
+                Binding::Gamepad(GamepadButtonType::South),
+            ],
+            pause: vec![
+                Binding::Key(KeyCode::Escape),
+                Binding::Gamepad(GamepadButtonType::Start),
+            ],
+            back: vec![
+                Binding::Key(KeyCode::Escape),
+                Binding::Gamepad(GamepadButtonType::East),
+            ],
+        }
+    }
+}
+
+impl InputBindings {
+    fn load() -> Self {
+        storage::load(BINDINGS_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(BINDINGS_FILE, self);
+    }
+
+    pub fn get(&self, action: Action) -> &Vec<Binding> {
+        match action {
+            Action::Jump => &self.jump,
+            Action::Confirm => &self.confirm,
+            Action::Pause => &self.pause,
+            Action::Back => &self.back,
+        }
+    }
+
+    /// Binds the button to the action in place of the action's button on the same device
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        let bindings = match action {
+            Action::Jump => &mut self.jump,
+            Action::Confirm => &mut self.confirm,
+            Action::Pause => &mut self.pause,
+            Action::Back => &mut self.back,
+        };
+        let device = std::mem::discriminant(&binding);
+        match bindings
+            .iter_mut()
+            .find(|x| std::mem::discriminant(*x) == device)
+        {
+            Some(old) => *old = binding,
+            None => bindings.push(binding),
+        }
+    }
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Binding::Key(k) => write!(f, "{k:?}"),
+            Binding::Mouse(m) => write!(f, "Mouse {m:?}"),
+            Binding::Gamepad(b) => write!(f, "Pad {b:?}"),
+        }
+    }
+}
+
+/// Current state of the input devices
+#[derive(SystemParam)]
+pub struct Devices<'w, 's> {
+    keys: Res<'w, Input<KeyCode>>,
+    mouse: Res<'w, Input<MouseButton>>,
+    gamepads: Res<'w, Gamepads>,
+    buttons: Res<'w, Input<GamepadButton>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl Devices<'_, '_> {
+    fn just_pressed(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::Key(k) => self.keys.just_pressed(*k),
+            Binding::Mouse(m) => self.mouse.just_pressed(*m),
+            Binding::Gamepad(b) => self
+                .gamepads
+                .iter()
+                .any(|pad| self.buttons.just_pressed(GamepadButton::new(pad, *b))),
+        }
+    }
+
+    fn pressed(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::Key(k) => self.keys.pressed(*k),
+            Binding::Mouse(m) => self.mouse.pressed(*m),
+            Binding::Gamepad(b) => self
+                .gamepads
+                .iter()
+                .any(|pad| self.buttons.pressed(GamepadButton::new(pad, *b))),
+        }
+    }
+
+    /// Any button pressed this frame, used for binding buttons to actions
+    pub fn any_just_pressed(&self) -> Option<Binding> {
+        if let Some(k) = self.keys.get_just_pressed().next() {
+            return Some(Binding::Key(*k));
+        }
+        if let Some(m) = self.mouse.get_just_pressed().next() {
+            return Some(Binding::Mouse(*m));
+        }
+        self.buttons
+            .get_just_pressed()
+            .next()
+            .map(|b| Binding::Gamepad(b.button_type))
+    }
+}
+
+fn update_actions(
+    mut actions: ResMut<Input<Action>>,
+    bindings: Res<InputBindings>,
+    devices: Devices,
+) {
+    actions.clear();
+    for action in Action::ALL {
+        let bound = bindings.get(action);
+        if bound.iter().any(|b| devices.just_pressed(b)) {
+            actions.press(action);
+        } else if !bound.iter().any(|b| devices.pressed(b)) {
+            actions.release(action);
+        }
+    }
+}
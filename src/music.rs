@@ -0,0 +1,113 @@
+use bevy::{asset::LoadState, audio::AudioSink, prelude::*};
+
+use crate::{
+    mixer::{AudioMixer, Bus},
+    ui::{Score, ScoreEvent},
+};
+
+/// Stems of the background music and the combo they join the mix at.
+/// They're all the same length and play in sync, the base stem is always audible.
+const MUSIC_STEMS: [(&str, i32); 4] = [
+    ("audio/music-base.ogg", 0),
+    ("audio/music-drums.ogg", 10),
+    ("audio/music-bass.ogg", 25),
+    ("audio/music-lead.ogg", 50),
+];
+/// How fast a stem fades in or out, in volume per second
+const FADE_IN_SPEED: f32 = 0.5;
+const FADE_OUT_SPEED: f32 = 2.0;
+
+/// Layered background music that gets more intense as the combo grows
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_music)
+            .add_system(start_music)
+            .add_system(mix_music.after(start_music));
+    }
+}
+
+#[derive(Resource)]
+struct Music {
+    stems: Vec<Stem>,
+    playing: bool,
+}
+
+struct Stem {
+    source: Handle<AudioSource>,
+    sink: Option<Handle<AudioSink>>,
+    /// Combo needed for the stem to be heard
+    threshold: i32,
+    /// Current volume of the stem before the mixer is applied
+    level: f32,
+}
+
+fn load_music(mut cmd: Commands, asset_server: Res<AssetServer>) {
+    let stems = MUSIC_STEMS
+        .iter()
+        .map(|(path, threshold)| Stem {
+            source: asset_server.load(*path),
+            sink: None,
+            threshold: *threshold,
+            level: 0.0,
+        })
+        .collect();
+    cmd.insert_resource(Music {
+        stems,
+        playing: false,
+    });
+}
+
+/// Starts all the stems together once they're loaded so they stay in sync
+fn start_music(
+    mut music: ResMut<Music>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    sinks: Res<Assets<AudioSink>>,
+) {
+    if music.playing {
+        return;
+    }
+    let sources = music.stems.iter().map(|x| x.source.id());
+    if asset_server.get_group_load_state(sources) != LoadState::Loaded {
+        return;
+    }
+    music.stems.iter_mut().for_each(|stem| {
+        let sink =
+            audio.play_with_settings(stem.source.clone(), PlaybackSettings::LOOP.with_volume(0.0));
+        stem.sink = Some(sinks.get_handle(sink));
+    });
+    music.playing = true;
+}
+
+fn mix_music(
+    mut music: ResMut<Music>,
+    mut events: EventReader<ScoreEvent>,
+    score: Res<Score>,
+    mixer: Res<AudioMixer>,
+    sinks: Res<Assets<AudioSink>>,
+    time: Res<Time>,
+) {
+    // losing the combo drops the extra stems out at once instead of fading them
+    let dropped = events.iter().any(|x| *x == ScoreEvent::ResetCombo);
+    let volume = mixer.volume(Bus::Music);
+    music.stems.iter_mut().for_each(|stem| {
+        let target = if score.current_combo >= stem.threshold {
+            1.0
+        } else {
+            0.0
+        };
+        stem.level = if dropped && stem.threshold > 0 {
+            0.0
+        } else if stem.level < target {
+            (stem.level + FADE_IN_SPEED * time.delta_seconds()).min(target)
+        } else {
+            (stem.level - FADE_OUT_SPEED * time.delta_seconds()).max(target)
+        };
+        let Some(sink) = stem.sink.as_ref().and_then(|x| sinks.get(x)) else {
+            return;
+        };
+        sink.set_volume(stem.level * volume);
+    });
+}
@@ -1,43 +1,81 @@
-use std::time::Duration;
+use std::{marker::PhantomData, time::Duration};
 
-use bevy::prelude::*;
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::*,
+};
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
 
 use crate::{
     cleanup::Dead,
-    game::{FadeOut, GameOverEvent, GameState, VIEW_BOX, ElapsedTime},
+    coins::spawn_coin,
+    definitions::{ObstacleDefinition, ObstacleDefinitions, SoundSet},
+    difficulty::Difficulty,
+    game::{not_paused, ElapsedTime, FadeOut, GameOverEvent, GameRng, GameState, VIEW_BOX},
+    mixer::{Bus, Sounds},
+    modes::GameMode,
     particles::{EmissionDirection, ParticleEmitter},
     physics::{
         Collider, CollisionEvent, FaceMovementDirection, Gravity, Movement, Projectile,
-        ProjectileCollisionEvent,
+        ProjectileCollisionEvent, SIMULATION_STEP, SIMULATION_STEP_DURATION,
     },
-    player::AttackState,
-    ui::{Score, ScoreEvent},
+    player::{AttackState, Player},
+    powerups::PowerUps,
+    ui::{Score, ScoreChange, ScoreSource},
 };
 
+/// Corpses only take down obstacles they come this close to
+pub const CORPSE_REACH: f32 = 100.0;
+
 pub struct ObstaclesPlugin;
 
 impl Plugin for ObstaclesPlugin {
     fn build(&self, app: &mut App) {
-        let start = SystemSet::on_enter(GameState::Playing).with_system(setup_obstacle_spawn_timer);
-        let update = SystemSet::on_update(GameState::Playing).with_system(spawn_birds);
+        let start = SystemSet::on_enter(GameState::Playing)
+            .with_system(setup_spawn_director)
+            .with_system(clear_obstacles);
 
         let cleanup = SystemSet::on_exit(GameState::End).with_system(cleanup_obstacles);
-
-        app.add_startup_system(load_birds)
-            .add_startup_system(setup_obstacle_spawn_timer)
-            .add_system_set(start)
-            .add_system_set(update)
-            .add_system_set(cleanup)
-            .add_system(bird_animation.before("cleanup"))
-            .add_system(spawn_tree_obstacles)
-            .add_system(spawn_cloud_obstacles)
-            .add_system(remove_obstacle.before("cleanup"))
-            .add_system(projectiles.after("projectiles"))
-            .add_system(
+        let world = SystemSet::new()
+            .with_run_criteria(not_paused)
+            .with_system(obstacle_animation.before("cleanup"))
+            .with_system(hit_flash);
+        let tick = SystemSet::new()
+            .with_run_criteria("simulation_tick")
+            .with_system(spawn_obstacles.label("spawn"))
+            .with_system(steer_homing.after(spawn_obstacles).before("movement"))
+            .with_system(boss_hover.after(spawn_obstacles).before("movement"))
+            .with_system(tick_invulnerability.before(obstacle_player_collision))
+            .with_system(
+                remove_obstacle
+                    .label("remove_obstacles")
+                    .after("movement")
+                    .before("score")
+                    .before("cleanup"),
+            )
+            .with_system(
+                projectiles
+                    .after("projectiles")
+                    .after(obstacle_player_collision)
+                    .before("score"),
+            )
+            .with_system(
                 obstacle_player_collision
+                    .label("player_collision")
+                    .before("score")
                     .before("game_over")
-                    .after("collision"),
+                    .after("collision")
+                    .after(spawn_obstacles),
             );
+
+        app.insert_resource(ObstacleDefinitions::load())
+            .add_startup_system(load_birds)
+            .add_startup_system(setup_spawn_director)
+            .add_system_set(start)
+            .add_system_set(cleanup)
+            .add_system_set(world)
+            .add_system_set(tick);
     }
 }
 
@@ -47,315 +85,430 @@ pub struct Obstacle {
     pub kind: ObstacleKind,
 }
 
-#[derive(Default, Clone, PartialEq)]
+#[derive(Default, Clone, PartialEq, Deserialize, Debug)]
 pub enum ObstacleKind {
     Tree,
     #[default]
     Bird,
     Cloud,
+    /// Bird that follows the barbarian's height
+    Eagle,
+    /// Boss that takes several hits
+    GiantBird,
 }
 
+/// Obstacle that takes several hits to go down
 #[derive(Component)]
-struct Bird;
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+    /// Seconds until it can be hit again
+    invulnerable: f32,
+}
 
+/// Flies in and stays to fight until it's defeated
 #[derive(Component)]
-struct Tree;
+pub struct Boss {
+    hover_x: f32,
+}
 
+/// Tints the sprite for a moment after a hit
 #[derive(Component)]
-struct Cloud;
+struct HitFlash {
+    timer: f32,
+}
 
-#[derive(Resource, Deref, DerefMut)]
-struct BirdSpawnTimer {
-    timer: Timer,
+#[derive(Component, Deserialize, Clone)]
+pub struct Homing {
+    /// Fastest vertical speed
+    pub speed: f32,
+    /// How much the vertical speed can change in a second
+    pub turn_rate: f32,
 }
 
-#[derive(Resource, Deref, DerefMut)]
-struct TreeSpawnTimer {
-    timer: Timer,
+/// Spawns obstacles from the spawn table, one clock per lane
+#[derive(Resource, Default)]
+struct SpawnDirector {
+    lanes: Vec<Lane>,
+    /// Score the next boss comes in at
+    next_boss: i32,
 }
 
-#[derive(Resource, Deref, DerefMut)]
-struct CloudSpawnTimer {
+struct Lane {
+    name: String,
     timer: Timer,
+    /// Indices of the lane's entries in the spawn table
+    entries: Vec<usize>,
+    ambient: bool,
 }
 
-#[derive(Resource, Default)]
-struct ObstacleAssets {
-    bird_fly_1: Handle<Image>,
-    bird_fly_2: Handle<Image>,
-    bird_dead: Handle<Image>,
-    tree_normal: Handle<Image>,
-    tree_dead: Handle<Image>,
-    cloud_normal: Handle<Image>,
-    cloud_dead: Handle<Image>,
-    bird_death_sounds: Vec<Handle<AudioSource>>,
-    bird_hit_sounds: Vec<Handle<AudioSource>>,
-    tree_death_sounds: Vec<Handle<AudioSource>>,
-    tree_hit_sounds: Vec<Handle<AudioSource>>,
-    cloud_death_sounds: Vec<Handle<AudioSource>>,
-    cloud_hit_sounds: Vec<Handle<AudioSource>>,
+/// How far the current run got
+#[derive(SystemParam)]
+struct RunProgress<'w, 's> {
+    state: Res<'w, State<GameState>>,
+    score: Res<'w, Score>,
+    elapsed: Res<'w, ElapsedTime>,
+    difficulty: Res<'w, Difficulty>,
+    mode: Res<'w, GameMode>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
 }
 
-impl ObstacleAssets {
-    pub const BIRD_SPRITE_SIZE_X: f32 = 128.;
-    pub const BIRD_SPRITE_SIZE_Y: f32 = 128.;
+impl RunProgress<'_, '_> {
+    /// Whether a run is being played, obstacles also spawn behind the menus
+    fn live(&self) -> bool {
+        *self.state.current() == GameState::Playing && !self.elapsed.finished
+    }
+}
 
-    pub const TREE_SPRITE_SIZE_X: f32 = 256.;
-    pub const TREE_SPRITE_SIZE_Y: f32 = 256.;
+/// Everything needed to resolve an obstacle getting hit
+#[derive(SystemParam)]
+struct ObstacleHit<'w, 's> {
+    cmd: Commands<'w, 's>,
+    assets: Res<'w, ObstacleAssets>,
+    defs: Res<'w, ObstacleDefinitions>,
+    sounds: Sounds<'w, 's>,
+    score: EventWriter<'w, 's, ScoreChange>,
+    health: Query<'w, 's, &'static mut Health>,
+    rng: ResMut<'w, GameRng>,
+    /// Obstacles already resolved this tick, several collisions in one tick can report the same obstacle
+    handled: Local<'s, Vec<Entity>>,
+}
 
-    pub const CLOUD_SPRITE_SIZE_X: f32 = 256.0;
-    pub const CLOUD_SPRITE_SIZE_Y: f32 = 256.0;
+#[derive(Resource)]
+struct ObstacleAssets {
+    bird: KindAssets,
+    tree: KindAssets,
+    cloud: KindAssets,
+    eagle: KindAssets,
+    giant_bird: KindAssets,
 }
 
-fn load_birds(mut cmd: Commands, asset_server: Res<AssetServer>) {
-    let mut bird_death_sounds = vec![];
-    for i in 1..=11 {
-        bird_death_sounds.push(asset_server.load(format!("audio/bird-death-{}.ogg", i)));
-    }
-    let mut bird_hit_sounds = vec![];
-    for i in 1..=5 {
-        bird_hit_sounds.push(asset_server.load(format!("audio/bird-hit-{}.ogg", i)));
-    }
-    let mut tree_death_sounds = vec![];
-    for i in 1..=2 {
-        tree_death_sounds.push(asset_server.load(format!("audio/tree-death-{}.ogg", i)));
-    }
-    let mut tree_hit_sounds = vec![];
-    for i in 1..=3 {
-        tree_hit_sounds.push(asset_server.load(format!("audio/tree-hit-{}.ogg", i)));
-    }
-    let mut cloud_death_sounds = vec![];
-    for i in 1..=2 {
-        cloud_death_sounds.push(asset_server.load(format!("audio/cloud-death-{}.ogg", i)));
-    }
-    let mut cloud_hit_sounds = vec![];
-    for i in 1..=2 {
-        cloud_hit_sounds.push(asset_server.load(format!("audio/cloud-hit-{}.ogg", i)));
-    }
+/// Sprites and sounds of one kind of obstacle, as listed in its definition
+struct KindAssets {
+    sprites: Vec<Handle<Image>>,
+    corpse: Handle<Image>,
+    hit_sounds: SoundChoices,
+    death_sounds: SoundChoices,
+}
 
-    let bs = ObstacleAssets {
-        bird_fly_1: asset_server.load("sprites/bird-fly-1.png"),
-        bird_fly_2: asset_server.load("sprites/bird-fly-2.png"),
-        bird_dead: asset_server.load("sprites/bird-dead.png"),
-        tree_normal: asset_server.load("sprites/tree-full.png"),
-        tree_dead: asset_server.load("sprites/tree-cut.png"),
-        cloud_normal: asset_server.load("sprites/cloud.png"),
-        cloud_dead: asset_server.load("sprites/cloud-cut.png"),
-        bird_death_sounds,
-        bird_hit_sounds,
-        tree_death_sounds,
-        tree_hit_sounds,
-        cloud_death_sounds,
-        cloud_hit_sounds,
-    };
-    cmd.insert_resource(bs);
+struct SoundChoices {
+    sounds: Vec<Handle<AudioSource>>,
+    volume: f32,
+}
+
+impl ObstacleAssets {
+    fn get(&self, kind: &ObstacleKind) -> &KindAssets {
+        match kind {
+            ObstacleKind::Bird => &self.bird,
+            ObstacleKind::Tree => &self.tree,
+            ObstacleKind::Cloud => &self.cloud,
+            ObstacleKind::Eagle => &self.eagle,
+            ObstacleKind::GiantBird => &self.giant_bird,
+        }
+    }
 }
 
-fn setup_obstacle_spawn_timer(mut cmd: Commands) {
-    let birb_time = BirdSpawnTimer {
-        timer: Timer::new(Duration::new(1, 0), TimerMode::Once),
+fn load_birds(mut cmd: Commands, asset_server: Res<AssetServer>, defs: Res<ObstacleDefinitions>) {
+    let load_sounds = |set: &SoundSet| SoundChoices {
+        sounds: set
+            .files
+            .iter()
+            .map(|x| asset_server.load(x.as_str()))
+            .collect(),
+        volume: set.volume,
+    };
+    let load_kind = |def: &ObstacleDefinition| KindAssets {
+        sprites: def
+            .sprites
+            .iter()
+            .map(|x| asset_server.load(x.as_str()))
+            .collect(),
+        corpse: asset_server.load(def.corpse.as_str()),
+        hit_sounds: load_sounds(&def.hit_sounds),
+        death_sounds: load_sounds(&def.death_sounds),
     };
-    cmd.insert_resource(birb_time);
 
-    let tree_time = TreeSpawnTimer {
-        timer: Timer::new(Duration::new(1, 0), TimerMode::Once),
+    let bs = ObstacleAssets {
+        bird: load_kind(&defs.bird),
+        tree: load_kind(&defs.tree),
+        cloud: load_kind(&defs.cloud),
+        eagle: load_kind(&defs.eagle),
+        giant_bird: load_kind(&defs.giant_bird),
     };
-    cmd.insert_resource(tree_time);
+    cmd.insert_resource(bs);
+}
 
-    let cloud_time = CloudSpawnTimer {
-        timer: Timer::new(Duration::new(1, 0), TimerMode::Once),
+fn setup_spawn_director(mut cmd: Commands, defs: Res<ObstacleDefinitions>) {
+    let mut director = SpawnDirector {
+        next_boss: defs.boss.every,
+        ..default()
     };
-    cmd.insert_resource(cloud_time);
+    defs.spawns.iter().enumerate().for_each(|(i, entry)| {
+        let lane = match director.lanes.iter_mut().find(|x| x.name == entry.lane) {
+            Some(o) => o,
+            None => {
+                director.lanes.push(Lane {
+                    name: entry.lane.clone(),
+                    timer: Timer::new(Duration::new(1, 0), TimerMode::Once),
+                    entries: vec![],
+                    ambient: false,
+                });
+                director.lanes.last_mut().unwrap()
+            }
+        };
+        lane.entries.push(i);
+        lane.ambient |= entry.ambient;
+    });
+    cmd.insert_resource(director);
 }
 
-fn spawn_birds(
+/// Lanes are handled in table order so runs stay reproducible
+fn spawn_obstacles(
     mut cmd: Commands,
-    sprites: Res<ObstacleAssets>,
-    mut timer: ResMut<BirdSpawnTimer>,
-    time: Res<Time>,
-    score: Res<Score>,
-    elapsed: Res<ElapsedTime>,
+    mut director: ResMut<SpawnDirector>,
+    assets: Res<ObstacleAssets>,
+    defs: Res<ObstacleDefinitions>,
+    run: RunProgress,
+    bosses: Query<(), With<Boss>>,
+    mut rng: ResMut<GameRng>,
 ) {
-    if timer.tick(time.delta()).just_finished() {
-        let variable_time = 1000000000 - score.score.min(1000) as u32 * 1000000; // scales spawning by how much score player achieved from 1 second to 0 at 1000 points
-        let random_time = rand::random::<u32>() % 500000000; // forms a range of time between 0 and 0.5 seconds
-        let duration = Duration::new(0, random_time + variable_time); // times are added together to create a random range between 0 and 1.5 seconds spawn interval time across the score range
-        timer.set_duration(duration);
-        timer.reset();
-        let rand_height: f32 = rand::random::<f32>();
-        let height = (rand_height * 0.6) + 0.2;
-        let height = VIEW_BOX.max.y * (1. - height) + VIEW_BOX.min.y * height;
-        let img = sprites.bird_fly_1.clone();
-        let random_speed = rand::random::<i32>() % 200;
-        let difficulty_meter = 0.55 + (elapsed.time / 1200.0).min(0.25); // max reached in 5 minutes
-        let sprite = (
-            SpriteBundle {
-                texture: img,
-                sprite: Sprite {
-                    custom_size: Some(Vec2 {
-                        x: ObstacleAssets::BIRD_SPRITE_SIZE_X,
-                        y: ObstacleAssets::BIRD_SPRITE_SIZE_Y,
-                    }),
-                    ..Default::default()
-                },
-                transform: Transform {
-                    translation: Vec3 {
-                        x: VIEW_BOX.max.x + ObstacleAssets::BIRD_SPRITE_SIZE_X,
-                        y: height,
-                        z: 0.,
-                    },
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-            Obstacle {
-                kind: ObstacleKind::Bird,
-                ..default()
-            },
-            Bird,
-            Collider {
-                collision_size: Vec2 {
-                    x: ObstacleAssets::BIRD_SPRITE_SIZE_X * 0.9,
-                    y: ObstacleAssets::BIRD_SPRITE_SIZE_Y * 0.9,
-                },
-                kill_size: ObstacleAssets::BIRD_SPRITE_SIZE_X * difficulty_meter,
-            },
-            Movement {
-                x: -400.0 - random_speed as f32 - score.current_combo.min(100) as f32 * 2.0,
-                y: 0.,
-            },
+    let live = run.live();
+    let RunProgress {
+        score,
+        elapsed,
+        difficulty: preset,
+        ..
+    } = &run;
+    let boss_fight = !bosses.is_empty();
+    if live && !boss_fight && score.score >= director.next_boss {
+        while director.next_boss <= score.score {
+            director.next_boss += defs.boss.every;
+        }
+        let kind = &defs.boss.kind;
+        let def = defs.get(kind);
+        let mut boss = cmd.spawn(obstacle_bundle(
+            def,
+            assets.get(kind),
+            kind.clone(),
+            (VIEW_BOX.min.y + VIEW_BOX.max.y) / 2.,
+            &run,
+            &mut rng,
+        ));
+        boss.insert(Boss {
+            hover_x: VIEW_BOX.min.x + VIEW_BOX.width() * defs.boss.hover,
+        });
+        insert_behaviours(&mut boss, def);
+    }
+    // only the scenery keeps spawning during a boss fight
+    let spawning = live && !boss_fight;
+    director.lanes.iter_mut().for_each(|lane| {
+        if !spawning && !lane.ambient {
+            return;
+        }
+        if !lane.timer.tick(SIMULATION_STEP_DURATION).just_finished() {
+            return;
+        }
+        let candidates = lane
+            .entries
+            .iter()
+            .map(|x| &defs.spawns[*x])
+            .filter(|x| (spawning && score.score >= x.min_score) || x.ambient)
+            .collect::<Vec<_>>();
+        let Ok(entry) = candidates.choose_weighted(&mut rng.gameplay, |x| x.weight) else {
+            // nothing in the lane can spawn yet, it checks again after its shortest interval
+            let shortest = lane
+                .entries
+                .iter()
+                .map(|x| defs.spawns[*x].interval.0)
+                .fold(f32::INFINITY, f32::min);
+            lane.timer.set_duration(Duration::from_secs_f32(shortest));
+            lane.timer.reset();
+            return;
+        };
+        let difficulty = entry.difficulty.progress(score.score, elapsed.time);
+        let (min, max) = match entry.hard_interval {
+            Some(hard) => (
+                entry.interval.0 + (hard.0 - entry.interval.0) * difficulty,
+                entry.interval.1 + (hard.1 - entry.interval.1) * difficulty,
+            ),
+            None => entry.interval,
+        };
+        // the preset only tunes runs, the scenery behind the menus keeps its pace
+        let scale = if live { preset.spawn_interval() } else { 1.0 };
+        let interval = rng.gameplay.gen_range(min..=max) * scale;
+        lane.timer.set_duration(Duration::from_secs_f32(interval));
+        lane.timer.reset();
+
+        let def = defs.get(&entry.kind);
+        let band = rng.gameplay.gen_range(entry.height.0..=entry.height.1);
+        // sprites are kept inside the view
+        let height = (VIEW_BOX.min.y + VIEW_BOX.height() * band).clamp(
+            VIEW_BOX.min.y + def.size.y / 2.,
+            VIEW_BOX.max.y - def.size.y / 2.,
         );
-        cmd.spawn(sprite);
+        let mut obstacle = cmd.spawn(obstacle_bundle(
+            def,
+            assets.get(&entry.kind),
+            entry.kind.clone(),
+            height,
+            &run,
+            &mut rng,
+        ));
+        insert_behaviours(&mut obstacle, def);
+    });
+}
+
+/// Components for the optional parts of a definition
+fn insert_behaviours(obstacle: &mut EntityCommands, def: &ObstacleDefinition) {
+    if let Some(homing) = &def.homing {
+        obstacle.insert((
+            homing.clone(),
+            FaceMovementDirection {
+                neutral: Vec2::NEG_X,
+            },
+        ));
+    }
+    if let Some(health) = def.health {
+        obstacle.insert(Health {
+            current: health,
+            max: health,
+            invulnerable: 0.,
+        });
     }
 }
 
-fn bird_animation(
-    mut cmd: Commands,
-    sprites: Res<ObstacleAssets>,
-    birds: Query<(Entity, &Transform), (With<Bird>, With<Obstacle>, Without<Dead>)>,
-) {
-    birds.for_each(|x| {
-        let Some(mut cmd) = cmd.get_entity(x.0) else {
-            return;
-        };
-        let tick = x.1.translation.x.abs() as i32 % 200;
-        match tick >= 100 {
-            true => {
-                cmd.insert(sprites.bird_fly_1.clone());
-            }
-            false => {
-                cmd.insert(sprites.bird_fly_2.clone());
-            }
+/// Bosses stop flying in once they reach their spot
+fn boss_hover(mut bosses: Query<(&mut Movement, &Transform, &Boss)>) {
+    bosses.for_each_mut(|(mut movement, transform, boss)| {
+        if transform.translation.x <= boss.hover_x {
+            movement.x = 0.;
         }
-    })
+    });
 }
 
-fn spawn_tree_obstacles(
+fn tick_invulnerability(mut health: Query<&mut Health>) {
+    health.for_each_mut(|mut health| {
+        health.invulnerable = (health.invulnerable - SIMULATION_STEP).max(0.);
+    });
+}
+
+fn hit_flash(
     mut cmd: Commands,
-    sprites: Res<ObstacleAssets>,
-    mut timer: ResMut<TreeSpawnTimer>,
+    mut flashing: Query<(Entity, &mut HitFlash, &mut Sprite, &Obstacle)>,
+    defs: Res<ObstacleDefinitions>,
     time: Res<Time>,
 ) {
-    if timer.tick(time.delta()).just_finished() {
-        let duration = Duration::new(1, rand::random::<u32>());
-        timer.set_duration(duration);
-        timer.reset();
-        let height = VIEW_BOX.min.y + ObstacleAssets::TREE_SPRITE_SIZE_Y / 2.;
-
-        let img = sprites.tree_normal.clone();
-        let sprite = (
-            SpriteBundle {
-                texture: img,
-                sprite: Sprite {
-                    custom_size: Some(Vec2 {
-                        x: ObstacleAssets::TREE_SPRITE_SIZE_X,
-                        y: ObstacleAssets::TREE_SPRITE_SIZE_Y,
-                    }),
-                    ..Default::default()
-                },
-                transform: Transform {
-                    translation: Vec3 {
-                        x: VIEW_BOX.max.x + ObstacleAssets::TREE_SPRITE_SIZE_X,
-                        y: height,
-                        z: 0.,
-                    },
-                    ..Default::default()
-                },
+    flashing.for_each_mut(|(entity, mut flash, mut sprite, obstacle)| {
+        flash.timer -= time.delta_seconds();
+        if flash.timer > 0. {
+            sprite.color = Color::RED;
+        } else {
+            sprite.color = defs.get(&obstacle.kind).tint.unwrap_or_default();
+            cmd.entity(entity).remove::<HitFlash>();
+        }
+    });
+}
+
+/// Obstacle entering the view from the right at the given height
+fn obstacle_bundle(
+    def: &ObstacleDefinition,
+    assets: &KindAssets,
+    kind: ObstacleKind,
+    height: f32,
+    run: &RunProgress,
+    rng: &mut GameRng,
+) -> impl Bundle {
+    let RunProgress {
+        score,
+        elapsed,
+        difficulty,
+        mode,
+        ..
+    } = run;
+    // variance under a whole unit has nothing to pick from
+    let variance = def.speed_variance as i32;
+    let random_speed = if variance > 0 {
+        (rng.gameplay.gen::<i32>() % variance) as f32
+    } else {
+        0.0
+    };
+    let difficulty_meter = match def.kill_size_max {
+        // max reached in 5 minutes
+        Some(max) => def.kill_size + (elapsed.time / 300.0).min(1.0) * (max - def.kill_size),
+        None => def.kill_size,
+    };
+    (
+        SpriteBundle {
+            texture: assets.sprites[0].clone(),
+            sprite: Sprite {
+                custom_size: Some(def.size),
+                color: def.tint.unwrap_or_default(),
                 ..Default::default()
             },
-            Obstacle {
-                kind: ObstacleKind::Tree,
-                ..default()
-            },
-            Tree,
-            Collider {
-                collision_size: Vec2 {
-                    x: ObstacleAssets::TREE_SPRITE_SIZE_X * 0.9,
-                    y: ObstacleAssets::TREE_SPRITE_SIZE_Y * 0.9,
+            transform: Transform {
+                translation: Vec3 {
+                    x: VIEW_BOX.max.x + def.size.x,
+                    y: height,
+                    z: 0.,
                 },
-                kill_size: ObstacleAssets::TREE_SPRITE_SIZE_X * 0.4,
+                ..Default::default()
             },
-            Movement { x: -200., y: 0. },
-        );
-        cmd.spawn(sprite);
-    }
+            ..Default::default()
+        },
+        Obstacle { kind, ..default() },
+        Collider {
+            collision_size: def.size * def.collider,
+            kill_size: def.size.x * difficulty_meter * difficulty.kill_size() * mode.kill_size(),
+        },
+        Movement {
+            x: -def.speed - random_speed - score.current_combo.min(100) as f32 * def.combo_speed,
+            y: 0.,
+        },
+    )
 }
 
-fn spawn_cloud_obstacles(
+/// Homing obstacles turn towards the barbarian's height until they pass him
+fn steer_homing(
+    mut obstacles: Query<(&mut Movement, &Transform, &Homing), With<Obstacle>>,
+    player: Query<&Transform, With<Player>>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    obstacles.for_each_mut(|(mut movement, transform, homing)| {
+        if transform.translation.x < player.translation.x {
+            return;
+        }
+        let target = ((player.translation.y - transform.translation.y) * 2.0)
+            .clamp(-homing.speed, homing.speed);
+        let turn = homing.turn_rate * SIMULATION_STEP;
+        movement.y += (target - movement.y).clamp(-turn, turn);
+    });
+}
+
+fn obstacle_animation(
     mut cmd: Commands,
     assets: Res<ObstacleAssets>,
-    time: Res<Time>,
-    mut timer: ResMut<CloudSpawnTimer>,
+    obstacles: Query<(Entity, &Transform, &Obstacle), Without<Dead>>,
 ) {
-    if timer.tick(time.delta()).just_finished() {
-        let duration = Duration::new(3, rand::random::<u32>());
-        timer.set_duration(duration);
-        timer.reset();
-        let img = assets.cloud_normal.clone();
-        let cloud = (
-            SpriteBundle {
-                texture: img,
-                sprite: Sprite {
-                    custom_size: Some(Vec2 {
-                        x: ObstacleAssets::CLOUD_SPRITE_SIZE_X,
-                        y: ObstacleAssets::CLOUD_SPRITE_SIZE_Y,
-                    }),
-                    ..default()
-                },
-                transform: Transform {
-                    translation: Vec3 {
-                        x: VIEW_BOX.max.x + ObstacleAssets::CLOUD_SPRITE_SIZE_X,
-                        y: VIEW_BOX.max.y
-                            - ObstacleAssets::CLOUD_SPRITE_SIZE_Y
-                                * (rand::random::<f32>() * 0.3 + 0.5),
-                        ..default()
-                    },
-                    ..default()
-                },
-                ..default()
-            },
-            Obstacle {
-                kind: ObstacleKind::Cloud,
-                ..default()
-            },
-            Cloud,
-            Collider {
-                collision_size: Vec2 {
-                    x: ObstacleAssets::CLOUD_SPRITE_SIZE_X * 0.6,
-                    y: ObstacleAssets::CLOUD_SPRITE_SIZE_Y * 0.6,
-                },
-                kill_size: ObstacleAssets::CLOUD_SPRITE_SIZE_X * 0.3,
-            },
-            Movement { x: -100.0, y: 0.0 },
-        );
-        cmd.spawn(cloud);
-    }
+    obstacles.for_each(|x| {
+        let frames = &assets.get(&x.2.kind).sprites;
+        if x.2.defeated || frames.len() < 2 {
+            return;
+        }
+        let Some(mut cmd) = cmd.get_entity(x.0) else {
+            return;
+        };
+        let frame = x.1.translation.x.abs() as usize / 100 % frames.len();
+        cmd.insert(frames[frame].clone());
+    })
 }
 
 fn remove_obstacle(
     mut cmd: Commands,
     obstacles: Query<(Entity, &Transform, &Obstacle)>,
-    mut ev: EventWriter<ScoreEvent>,
+    mut ev: EventWriter<ScoreChange>,
 ) {
     obstacles
         .iter()
@@ -367,185 +520,202 @@ fn remove_obstacle(
             cmd.entity(x.0)
                 .remove::<Obstacle>()
                 .insert(Dead { timer: 1.0 });
-            if x.2.defeated == false && x.2.kind == ObstacleKind::Bird {
-                ev.send(ScoreEvent::ResetCombo)
+            if x.2.defeated == false && matches!(x.2.kind, ObstacleKind::Bird | ObstacleKind::Eagle)
+            {
+                ev.send(ScoreChange::ResetCombo)
             }
         });
 }
 
 fn obstacle_player_collision(
-    mut cmd: Commands,
     mut ev: EventReader<CollisionEvent>,
-    assets: Res<ObstacleAssets>,
-    mut score: EventWriter<ScoreEvent>,
+    mut hit: ObstacleHit,
+    mut players: Query<&mut Player>,
+    mut power_ups: ResMut<PowerUps>,
     mut game_over: EventWriter<GameOverEvent>,
-    audio: Res<Audio>,
+    mode: Res<GameMode>,
 ) {
+    hit.handled.clear();
     ev.iter().for_each(|o| {
+        let Ok(mut player) = players.get_mut(o.player) else {
+            return;
+        };
         if o.player_state == AttackState::NotAttacking {
-            if o.is_deadly {
+            // grazes are harmless and leave the shield up, the blink after a hit shrugs off the rest
+            if !o.is_deadly || player.is_invulnerable() {
+                return;
+            }
+            let shielded = !mode.collisions_end_run() || power_ups.absorb_hit();
+            if !player.hurt(shielded) {
                 game_over.send_default();
             }
             return;
         }
         obstacle_collision_handle(
-            &mut cmd,
-            &assets,
-            &audio,
-            &mut score,
+            &mut hit,
             o.obstacle,
             &o.obstacle_kind,
             o.obstacle_pos,
             o.player_pos,
             true,
+            0,
         );
     });
 }
 
-fn projectiles(
-    mut cmd: Commands,
-    mut ev: EventReader<ProjectileCollisionEvent>,
-    assets: Res<ObstacleAssets>,
-    mut score: EventWriter<ScoreEvent>,
-    audio: Res<Audio>,
-) {
+fn projectiles(mut ev: EventReader<ProjectileCollisionEvent>, mut hit: ObstacleHit) {
+    hit.handled.clear();
     ev.iter().for_each(|e| {
-        if e.hit_pos.distance(e.projectile_pos) > 100.0 {
+        if e.hit_pos.distance(e.projectile_pos) > CORPSE_REACH {
             return;
         }
         obstacle_collision_handle(
-            &mut cmd,
-            &assets,
-            &audio,
-            &mut score,
+            &mut hit,
             e.hit,
             &e.hit_kind,
             e.hit_pos,
             e.projectile_pos,
             false,
+            e.chain,
         );
     });
 }
 
 fn obstacle_collision_handle(
-    cmd: &mut Commands,
-    assets: &Res<ObstacleAssets>,
-    audio: &Res<Audio>,
-    score: &mut EventWriter<ScoreEvent>,
+    hit: &mut ObstacleHit,
     obstacle: Entity,
     obstacle_kind: &ObstacleKind,
     obstacle_pos: Vec3,
     hit_pos: Vec3,
     is_player_collision: bool,
+    chain: u32,
 ) {
-    let x = (obstacle_pos.x - hit_pos.x) * (rand::random::<f32>() + 1.0);
-    let y = (obstacle_pos.y - hit_pos.y) * (rand::random::<f32>() + 1.0);
+    if hit.handled.contains(&obstacle) {
+        return;
+    }
+    hit.handled.push(obstacle);
+    let x = (obstacle_pos.x - hit_pos.x) * (hit.rng.gameplay.gen::<f32>() + 1.0);
+    let y = (obstacle_pos.y - hit_pos.y) * (hit.rng.gameplay.gen::<f32>() + 1.0);
     let force = Vec2 { x, y }.normalize() * 1000.0;
     let hit_location = (obstacle_pos + hit_pos) / 2.0;
+    let ObstacleHit {
+        cmd,
+        assets,
+        defs,
+        sounds,
+        score,
+        health,
+        rng,
+        ..
+    } = hit;
+
+    // the kill is the next link of the chain of whatever hit the obstacle
+    let link = chain + 1;
+    let kill = ScoreChange::Kill {
+        position: obstacle_pos,
+        source: ScoreSource::from_chain(link),
+        kind: obstacle_kind.clone(),
+    };
+
+    // obstacles with health only go down on the last hit
+    if let Ok(mut health) = health.get_mut(obstacle) {
+        if health.invulnerable > 0. {
+            return;
+        }
+        health.current -= 1;
+        if health.current > 0 {
+            health.invulnerable = defs.get(obstacle_kind).invulnerability;
+            cmd.entity(obstacle).insert(HitFlash { timer: 0.15 });
+            spawn_hit(cmd, Color::RED, hit_location, force);
+            play_sound(sounds, &assets.get(obstacle_kind).hit_sounds, rng);
+            return;
+        }
+    }
 
     match obstacle_kind {
         ObstacleKind::Tree => {
             cmd.entity(obstacle)
                 .remove::<Obstacle>()
                 .insert(Dead::default());
-            spawn_tree_corpse(cmd, &assets, obstacle_pos);
+            spawn_tree_corpse(cmd, &defs.tree, &assets.tree, obstacle_pos);
             spawn_hit(cmd, Color::GREEN, hit_location, force);
-            play_death_sound(&audio, &assets, ObstacleKind::Tree);
-            play_hit_sound(&audio, &assets, ObstacleKind::Tree);
+            play_sound(sounds, &assets.get(&ObstacleKind::Tree).death_sounds, rng);
+            play_sound(sounds, &assets.get(&ObstacleKind::Tree).hit_sounds, rng);
         }
         ObstacleKind::Bird => {
             cmd.entity(obstacle)
                 .remove::<Obstacle>()
                 .insert(Dead::default());
-            spawn_bird_corpse(cmd, &assets, obstacle_pos, force);
+            spawn_bird_corpse(cmd, &defs.bird, &assets.bird, obstacle_pos, force, link);
+            drop_coin(cmd, &defs.bird, rng, obstacle_pos);
             spawn_hit(cmd, Color::RED, hit_location, force);
-            score.send(ScoreEvent::Add);
-            play_death_sound(&audio, &assets, ObstacleKind::Bird);
-            play_hit_sound(&audio, &assets, ObstacleKind::Bird);
+            score.send(kill);
+            play_sound(sounds, &assets.get(&ObstacleKind::Bird).death_sounds, rng);
+            play_sound(sounds, &assets.get(&ObstacleKind::Bird).hit_sounds, rng);
+        }
+        ObstacleKind::Eagle => {
+            cmd.entity(obstacle)
+                .remove::<Obstacle>()
+                .insert(Dead::default());
+            spawn_eagle_corpse(cmd, &defs.eagle, &assets.eagle, obstacle_pos, force, link);
+            drop_coin(cmd, &defs.eagle, rng, obstacle_pos);
+            spawn_hit(cmd, Color::ORANGE_RED, hit_location, force);
+            score.send(kill);
+            play_sound(sounds, &assets.get(&ObstacleKind::Eagle).death_sounds, rng);
+            play_sound(sounds, &assets.get(&ObstacleKind::Eagle).hit_sounds, rng);
+        }
+        ObstacleKind::GiantBird => {
+            cmd.entity(obstacle)
+                .remove::<Obstacle>()
+                .remove::<Boss>()
+                .insert(Dead::default());
+            spawn_bird_corpse(
+                cmd,
+                &defs.giant_bird,
+                &assets.giant_bird,
+                obstacle_pos,
+                force,
+                link,
+            );
+            drop_coin(cmd, &defs.giant_bird, rng, obstacle_pos);
+            spawn_hit(cmd, Color::RED, hit_location, force);
+            score.send(kill);
+            play_sound(
+                sounds,
+                &assets.get(&ObstacleKind::GiantBird).death_sounds,
+                rng,
+            );
+            play_sound(
+                sounds,
+                &assets.get(&ObstacleKind::GiantBird).hit_sounds,
+                rng,
+            );
         }
         ObstacleKind::Cloud if is_player_collision => {
             cmd.entity(obstacle)
                 .remove::<Obstacle>()
                 .insert(Dead::default());
-            spawn_cloud_corpse(cmd, &assets, obstacle_pos, force);
+            spawn_cloud_corpse(cmd, &defs.cloud, &assets.cloud, obstacle_pos, force);
             spawn_hit(cmd, Color::WHITE, hit_location, force);
-            play_death_sound(&audio, &assets, ObstacleKind::Cloud);
-            play_hit_sound(&audio, &assets, ObstacleKind::Cloud);
+            play_sound(sounds, &assets.get(&ObstacleKind::Cloud).death_sounds, rng);
+            play_sound(sounds, &assets.get(&ObstacleKind::Cloud).hit_sounds, rng);
         }
         ObstacleKind::Cloud => {}
     }
 }
 
-fn play_death_sound(audio: &Res<Audio>, assets: &Res<ObstacleAssets>, obstacle: ObstacleKind) {
-    let sound = match obstacle {
-        ObstacleKind::Tree => (
-            assets
-                .tree_death_sounds
-                .get(rand::random::<usize>() % assets.tree_death_sounds.len())
-                .unwrap()
-                .clone(),
-            PlaybackSettings {
-                volume: 0.3,
-                ..default()
-            },
-        ),
-        ObstacleKind::Bird => (
-            assets
-                .bird_death_sounds
-                .get(rand::random::<usize>() % assets.bird_death_sounds.len())
-                .unwrap()
-                .clone(),
-            PlaybackSettings::default(),
-        ),
-        ObstacleKind::Cloud => (
-            assets
-                .cloud_death_sounds
-                .get(rand::random::<usize>() % assets.tree_death_sounds.len())
-                .unwrap()
-                .clone(),
-            PlaybackSettings {
-                volume: 0.5,
-                ..default()
-            },
-        ),
+fn play_sound(sounds: &Sounds, choices: &SoundChoices, rng: &mut GameRng) {
+    let Some(sound) = choices.sounds.choose(&mut rng.cosmetic) else {
+        return;
     };
-    audio.play_with_settings(sound.0, sound.1);
-}
-
-fn play_hit_sound(audio: &Res<Audio>, assets: &Res<ObstacleAssets>, obstacle: ObstacleKind) {
-    let sound = match obstacle {
-        ObstacleKind::Tree => (
-            assets
-                .tree_hit_sounds
-                .get(rand::random::<usize>() % assets.tree_hit_sounds.len())
-                .unwrap()
-                .clone(),
-            PlaybackSettings {
-                volume: 0.6,
-                ..default()
-            },
-        ),
-        ObstacleKind::Bird => (
-            assets
-                .bird_hit_sounds
-                .get(rand::random::<usize>() % assets.bird_hit_sounds.len())
-                .unwrap()
-                .clone(),
-            PlaybackSettings {
-                volume: 0.5,
-                ..default()
-            },
-        ),
-        ObstacleKind::Cloud => (
-            assets
-                .cloud_hit_sounds
-                .get(rand::random::<usize>() % assets.cloud_hit_sounds.len())
-                .unwrap()
-                .clone(),
-            PlaybackSettings::default(),
-        ),
-    };
-    audio.play_with_settings(sound.0, sound.1);
+    sounds.play(sound.clone(), Bus::Sfx, choices.volume);
+}
+
+/// Coins don't change how a run plays out, so they roll the cosmetic rng
+fn drop_coin(cmd: &mut Commands, def: &ObstacleDefinition, rng: &mut GameRng, location: Vec3) {
+    if rng.cosmetic.gen::<f32>() < def.coin_chance {
+        spawn_coin(cmd, location);
+    }
 }
 
 fn spawn_hit(cmd: &mut Commands, color: Color, location: Vec3, force: Vec2) {
@@ -561,19 +731,21 @@ fn spawn_hit(cmd: &mut Commands, color: Color, location: Vec3, force: Vec2) {
     ));
 }
 
-fn spawn_tree_corpse(cmd: &mut Commands, sprites: &ObstacleAssets, location: Vec3) {
+fn spawn_tree_corpse(
+    cmd: &mut Commands,
+    def: &ObstacleDefinition,
+    assets: &KindAssets,
+    location: Vec3,
+) {
     cmd.spawn((
         Obstacle {
             defeated: true,
             kind: ObstacleKind::Tree,
         },
         SpriteBundle {
-            texture: sprites.tree_dead.clone(),
+            texture: assets.corpse.clone(),
             sprite: Sprite {
-                custom_size: Some(Vec2 {
-                    x: ObstacleAssets::TREE_SPRITE_SIZE_X,
-                    y: ObstacleAssets::TREE_SPRITE_SIZE_Y,
-                }),
+                custom_size: Some(def.size),
                 ..Default::default()
             },
             transform: Transform {
@@ -586,19 +758,24 @@ fn spawn_tree_corpse(cmd: &mut Commands, sprites: &ObstacleAssets, location: Vec
     ));
 }
 
-fn spawn_bird_corpse(cmd: &mut Commands, sprites: &ObstacleAssets, location: Vec3, movement: Vec2) {
-    cmd.spawn((
+fn spawn_bird_corpse(
+    cmd: &mut Commands,
+    def: &ObstacleDefinition,
+    assets: &KindAssets,
+    location: Vec3,
+    movement: Vec2,
+    chain: u32,
+) {
+    let mut corpse = cmd.spawn((
         Obstacle {
             defeated: true,
             kind: ObstacleKind::Bird,
         },
         SpriteBundle {
-            texture: sprites.bird_dead.clone(),
+            texture: assets.corpse.clone(),
             sprite: Sprite {
-                custom_size: Some(Vec2 {
-                    x: ObstacleAssets::BIRD_SPRITE_SIZE_X,
-                    y: ObstacleAssets::BIRD_SPRITE_SIZE_Y,
-                }),
+                custom_size: Some(def.size),
+                color: def.tint.unwrap_or_default(),
                 ..Default::default()
             },
             transform: Transform {
@@ -611,12 +788,6 @@ fn spawn_bird_corpse(cmd: &mut Commands, sprites: &ObstacleAssets, location: Vec
             x: movement.x,
             y: movement.y,
         },
-        Projectile {
-            size: Vec2 {
-                x: ObstacleAssets::BIRD_SPRITE_SIZE_X * 0.9,
-                y: ObstacleAssets::BIRD_SPRITE_SIZE_Y * 0.7,
-            },
-        },
         Gravity::default(),
         FaceMovementDirection {
             neutral: Vec2 { x: 0., y: -1. },
@@ -625,11 +796,68 @@ fn spawn_bird_corpse(cmd: &mut Commands, sprites: &ObstacleAssets, location: Vec
             .with_color(Color::RED)
             .with_direction(EmissionDirection::Local(Vec2::Y)),
     ));
+    if let Some(collider) = def.corpse_collider {
+        corpse.insert(Projectile {
+            size: def.size * collider,
+            chain,
+        });
+    }
+}
+
+/// Eagles are heavy, their corpses are knocked back less and drop faster shedding feathers
+fn spawn_eagle_corpse(
+    cmd: &mut Commands,
+    def: &ObstacleDefinition,
+    assets: &KindAssets,
+    location: Vec3,
+    movement: Vec2,
+    chain: u32,
+) {
+    let tint = def.tint.unwrap_or_default();
+    let mut corpse = cmd.spawn((
+        Obstacle {
+            defeated: true,
+            kind: ObstacleKind::Eagle,
+        },
+        SpriteBundle {
+            texture: assets.corpse.clone(),
+            sprite: Sprite {
+                custom_size: Some(def.size),
+                color: tint,
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: location,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Movement {
+            x: movement.x * 0.5,
+            y: movement.y * 0.5,
+        },
+        Gravity {
+            strength: Vec2 { x: 0.5, y: 4. },
+        },
+        FaceMovementDirection {
+            neutral: Vec2 { x: 0., y: -1. },
+        },
+        ParticleEmitter::new(2, Duration::new(0, 50000), TimerMode::Repeating)
+            .with_color(tint)
+            .with_direction(EmissionDirection::Local(Vec2::Y)),
+    ));
+    if let Some(collider) = def.corpse_collider {
+        corpse.insert(Projectile {
+            size: def.size * collider,
+            chain,
+        });
+    }
 }
 
 fn spawn_cloud_corpse(
     cmd: &mut Commands,
-    sprites: &ObstacleAssets,
+    def: &ObstacleDefinition,
+    assets: &KindAssets,
     location: Vec3,
     movement: Vec2,
 ) {
@@ -644,8 +872,7 @@ fn spawn_cloud_corpse(
         };
         let i = i as f32;
         let location = Vec3 {
-            x: location.x - (ObstacleAssets::CLOUD_SPRITE_SIZE_X / 4.0)
-                + (ObstacleAssets::CLOUD_SPRITE_SIZE_X / 2.0 * i),
+            x: location.x - (def.size.x / 4.0) + (def.size.x / 2.0 * i),
             y: location.y,
             z: location.z,
         };
@@ -655,11 +882,11 @@ fn spawn_cloud_corpse(
                 kind: ObstacleKind::Cloud,
             },
             SpriteBundle {
-                texture: sprites.cloud_dead.clone(),
+                texture: assets.corpse.clone(),
                 sprite: Sprite {
                     custom_size: Some(Vec2 {
-                        x: ObstacleAssets::CLOUD_SPRITE_SIZE_X * 0.5,
-                        y: ObstacleAssets::CLOUD_SPRITE_SIZE_Y,
+                        x: def.size.x * 0.5,
+                        y: def.size.y,
                     }),
                     rect: Some(Rect {
                         min: Vec2 {
@@ -692,8 +919,21 @@ fn spawn_cloud_corpse(
     }
 }
 
-fn cleanup_obstacles(mut cmd: Commands, obs: Query<Entity, With<Bird>>) {
+/// Takes obstacles left over from the menu or the previous run out of play
+/// so every run starts from the same empty field
+fn clear_obstacles(mut cmd: Commands, obs: Query<Entity, With<Obstacle>>) {
     obs.for_each(|x| {
-        cmd.entity(x).insert(Dead::default());
+        cmd.entity(x)
+            .remove::<Obstacle>()
+            .remove::<Projectile>()
+            .remove::<Boss>()
+            .insert(FadeOut { speed: 2.0 })
+            .insert(Dead { timer: 0.5 });
+    });
+}
+
+fn cleanup_obstacles(mut cmd: Commands, obs: Query<(Entity, &Obstacle), Without<Dead>>) {
+    obs.iter().filter(|x| !x.1.defeated).for_each(|x| {
+        cmd.entity(x.0).insert(Dead::default());
     });
 }
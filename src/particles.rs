@@ -1,8 +1,12 @@
 use std::time::Duration;
 
 use bevy::prelude::*;
+use rand::Rng;
 
-use crate::physics::Movement;
+use crate::{
+    game::{not_paused, GameRng},
+    physics::Movement,
+};
 
 const PARTICLE_COUNT: u32 = 500;
 
@@ -10,14 +14,23 @@ pub struct ParticlePlugin;
 
 impl Plugin for ParticlePlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(spawn_particles)
-            .add_system(particle_opacity)
-            .add_system(particle_lifetime.before(particle_expire))
-            .add_system(particle_expire)
-            .add_system(emit_particles);
+        let update = SystemSet::new()
+            .with_run_criteria(not_paused)
+            .with_system(particle_opacity)
+            .with_system(particle_lifetime.before(particle_expire))
+            .with_system(particle_expire)
+            .with_system(emit_particles);
+
+        app.insert_resource(ParticleDensity(1.0))
+            .add_startup_system(spawn_particles)
+            .add_system_set(update);
     }
 }
 
+/// Fraction of the particles that emitters actually emit, set from the settings
+#[derive(Resource)]
+pub struct ParticleDensity(pub f32);
+
 pub enum EmissionDirection {
     Local(Vec2),
     Global(Vec2),
@@ -125,13 +138,17 @@ fn particle_lifetime(time: Res<Time>, mut particles: Query<&mut ActiveParticle>)
 fn emit_particles(
     mut cmd: Commands,
     time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+    density: Res<ParticleDensity>,
     mut emiters: Query<(&mut ParticleEmitter, &Transform), Without<InactiveParticle>>,
     mut particles: Query<(Entity, &InactiveParticle, &mut Sprite, &mut Transform)>,
 ) {
     let mut particles = particles.iter_mut();
     emiters.for_each_mut(|(mut emiter, transform)| {
         if emiter.interval.tick(time.delta()).just_finished() {
-            (0..emiter.emit_rate).for_each(|_| {
+            // the fraction left after scaling becomes a chance for one more particle
+            let count = (emiter.emit_rate as f32 * density.0 + rng.cosmetic.gen::<f32>()) as u32;
+            (0..count).for_each(|_| {
                 let Some(mut p) = particles.next() else {
                     return;
                 };
@@ -156,7 +173,7 @@ fn emit_particles(
 
                         EmissionDirection::Global(d) => d.normalize(),
                     };
-                    let random_angle = rand::random::<f32>() - 0.5;
+                    let random_angle = rng.cosmetic.gen::<f32>() - 0.5;
                     let dir = Vec2::from_angle(random_angle).rotate(dir) * emiter.speed;
                     (dir.x, dir.y)
                 };
@@ -1,10 +1,13 @@
+use std::time::Duration;
+
 use bevy::{
-    prelude::*,
-    sprite::collide_aabb::{collide, Collision},
+    ecs::schedule::ShouldRun, prelude::*, sprite::collide_aabb::collide, transform::TransformSystem,
 };
 
 use crate::{
     cleanup::Dead,
+    difficulty::Difficulty,
+    game::GameState,
     obstacles::{Obstacle, ObstacleKind},
     player::{AttackState, Player},
 };
@@ -13,18 +16,97 @@ pub struct PhysicsPlugin;
 
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<CollisionEvent>()
-            .add_event::<ProjectileCollisionEvent>()
-            .add_system(gravity.label("gravity").before("movement"))
-            .add_system(face_movement_direction.after("gravity"))
-            .add_system(
+        let simulation = SystemSet::new()
+            .with_run_criteria(simulation_tick.label("simulation_tick"))
+            .with_system(gravity.label("gravity").before("movement"))
+            .with_system(face_movement_direction.after("gravity"))
+            .with_system(store_previous_positions.before("movement"))
+            .with_system(
                 move_bodies
                     .label("movement")
                     .before("collision")
                     .before("projectiles"),
             )
-            .add_system(collision_detection.label("collision"))
-            .add_system(projectile_collision.label("projectiles"));
+            .with_system(collision_detection.label("collision"))
+            .with_system(projectile_collision.label("projectiles"));
+
+        app.add_event::<CollisionEvent>()
+            .add_event::<ProjectileCollisionEvent>()
+            .init_resource::<SimulationClock>()
+            .add_system_set(simulation)
+            .add_system_to_stage(CoreStage::PreUpdate, restore_simulated_positions)
+            .add_system_to_stage(CoreStage::PostUpdate, start_interpolation)
+            .add_system_to_stage(CoreStage::PostUpdate, stop_interpolation)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                interpolate_positions.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Length of a single simulation tick in seconds
+pub const SIMULATION_STEP: f32 = 1.0 / 60.0;
+pub const SIMULATION_STEP_DURATION: Duration = Duration::from_nanos(16_666_667);
+/// Most ticks simulated in one frame, past that the simulation slows down instead of spiraling
+const MAX_TICKS_PER_FRAME: u32 = 5;
+
+/// Accumulates frame time and hands it out to the simulation in fixed ticks
+#[derive(Resource)]
+pub struct SimulationClock {
+    accumulator: f64,
+    ticks_this_frame: u32,
+    looping: bool,
+    /// Amount of ticks simulated since startup
+    pub tick: u64,
+    /// How fast the simulation runs compared to real time
+    pub time_scale: f32,
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        Self {
+            accumulator: 0.0,
+            ticks_this_frame: 0,
+            looping: false,
+            tick: 0,
+            time_scale: 1.0,
+        }
+    }
+}
+
+impl SimulationClock {
+    /// How far the frame is between the last tick and the next one, from 0 to 1
+    pub fn overstep(&self) -> f32 {
+        (self.accumulator / SIMULATION_STEP as f64).min(1.0) as f32
+    }
+}
+
+/// Run criteria for systems that advance the simulation, runs them once per elapsed tick.
+/// Other sets join the same ticks through the "simulation_tick" label.
+fn simulation_tick(
+    state: Res<State<GameState>>,
+    time: Res<Time>,
+    mut clock: ResMut<SimulationClock>,
+) -> ShouldRun {
+    if !clock.looping {
+        if *state.current() == GameState::Paused {
+            return ShouldRun::No;
+        }
+        clock.accumulator += time.delta_seconds_f64() * clock.time_scale as f64;
+        clock.ticks_this_frame = 0;
+    }
+    let step = SIMULATION_STEP as f64;
+    if clock.accumulator >= step && clock.ticks_this_frame < MAX_TICKS_PER_FRAME {
+        clock.accumulator -= step;
+        clock.ticks_this_frame += 1;
+        clock.tick += 1;
+        clock.looping = true;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        // a hitch longer than the tick limit is dropped rather than caught up on later
+        clock.accumulator = clock.accumulator.min(step);
+        clock.looping = false;
+        ShouldRun::No
     }
 }
 
@@ -44,6 +126,8 @@ pub struct Collider {
 #[derive(Component)]
 pub struct Projectile {
     pub size: Vec2,
+    /// Kills in the chain that made this corpse, the barbarian's own kill is the first
+    pub chain: u32,
 }
 
 #[derive(Component, Default)]
@@ -52,6 +136,13 @@ pub struct Movement {
     pub y: f32,
 }
 
+/// Positions of a body at the last two ticks, the sprite is drawn between them
+#[derive(Component)]
+pub struct Interpolated {
+    previous: Vec3,
+    current: Vec3,
+}
+
 #[derive(Component)]
 pub struct FaceMovementDirection {
     /// The direction sprite faces naturally
@@ -76,7 +167,6 @@ impl Gravity {
 }
 
 pub struct CollisionEvent {
-    pub collision: Collision,
     pub player_state: AttackState,
     pub player: Entity,
     pub player_pos: Vec3,
@@ -91,6 +181,8 @@ pub struct ProjectileCollisionEvent {
     pub hit: Entity,
     pub hit_pos: Vec3,
     pub hit_kind: ObstacleKind,
+    /// Chain of the projectile that hit
+    pub chain: u32,
 }
 
 fn collision_detection(
@@ -106,22 +198,18 @@ fn collision_detection(
 
     obstacles
         .iter()
-        .filter_map(|x| {
+        .filter(|x| {
             let o_pos = x.1.translation;
             let o_size = x.0.collision_size;
-            let Some(c) = collide(o_pos, o_size, p_pos, p_size) else {
-            return None;
-        };
-            Some((c, x))
+            collide(o_pos, o_size, p_pos, p_size).is_some()
         })
-        .for_each(|(collision, x)| {
+        .for_each(|x| {
             let is_deadly = if p_pos.distance(x.1.translation) < x.0.kill_size {
                 true
             } else {
                 false
             };
             let ev = CollisionEvent {
-                collision,
                 player_state: pl.2.attack_state.clone(),
                 player: pl.3,
                 player_pos: p_pos,
@@ -151,28 +239,30 @@ fn projectile_collision(
                     hit: obs_entity.clone(),
                     hit_pos: obs_pos,
                     hit_kind: obstacle.kind.clone(),
+                    chain: projectile.chain,
                 })
             }
         })
     })
 }
 
-fn move_bodies(time: Res<Time>, mut bodies: Query<(&Movement, &mut Transform)>) {
+fn move_bodies(mut bodies: Query<(&Movement, &mut Transform)>) {
     bodies.for_each_mut(|mut o| {
-        let (x, y) = (o.0.x * time.delta_seconds(), o.0.y * time.delta_seconds());
+        let (x, y) = (o.0.x * SIMULATION_STEP, o.0.y * SIMULATION_STEP);
         o.1.translation.x += x;
         o.1.translation.y += y;
     })
 }
 
-fn gravity(time: Res<Time>, mut affected: Query<(&mut Movement, &Gravity)>) {
+fn gravity(mut affected: Query<(&mut Movement, &Gravity)>, difficulty: Res<Difficulty>) {
+    let max_gravity = Gravity::MAX_GRAVITY * difficulty.gravity();
     affected.for_each_mut(|mut o| {
         let speed = Vec2 {
-            x: o.1.x * time.delta_seconds(),
-            y: o.1.y * time.delta_seconds(),
+            x: o.1.x * SIMULATION_STEP,
+            y: o.1.y * SIMULATION_STEP,
         };
         o.0.x = o.0.x * (1.0 - speed.x);
-        o.0.y = o.0.y * (1.0 - speed.y) + (Gravity::MAX_GRAVITY * speed.y);
+        o.0.y = o.0.y * (1.0 - speed.y) + (max_gravity * speed.y);
     });
 }
 
@@ -184,3 +274,47 @@ fn face_movement_direction(mut bodies: Query<(&mut Transform, &Movement, &FaceMo
         o.0.rotation = Quat::from_euler(EulerRot::XYZ, 0., 0., angle);
     })
 }
+
+fn start_interpolation(mut cmd: Commands, bodies: Query<(Entity, &Transform), Added<Movement>>) {
+    bodies.for_each(|(entity, transform)| {
+        cmd.entity(entity).insert(Interpolated {
+            previous: transform.translation,
+            current: transform.translation,
+        });
+    });
+}
+
+/// Bodies that stopped moving can be placed anywhere by the game, so they're drawn where they are
+fn stop_interpolation(
+    mut cmd: Commands,
+    bodies: Query<Entity, (With<Interpolated>, Without<Movement>)>,
+) {
+    bodies.for_each(|entity| {
+        cmd.entity(entity).remove::<Interpolated>();
+    });
+}
+
+fn store_previous_positions(mut bodies: Query<(&Transform, &mut Interpolated)>) {
+    bodies.for_each_mut(|(transform, mut interpolated)| {
+        interpolated.previous = transform.translation;
+    });
+}
+
+/// Moves the sprites between the last two ticks for drawing
+fn interpolate_positions(
+    clock: Res<SimulationClock>,
+    mut bodies: Query<(&mut Transform, &mut Interpolated)>,
+) {
+    let overstep = clock.overstep();
+    bodies.for_each_mut(|(mut transform, mut interpolated)| {
+        interpolated.current = transform.translation;
+        transform.translation = interpolated.previous.lerp(interpolated.current, overstep);
+    });
+}
+
+/// Puts the bodies back on their simulated positions before the game logic runs
+fn restore_simulated_positions(mut bodies: Query<(&mut Transform, &Interpolated)>) {
+    bodies.for_each_mut(|(mut transform, interpolated)| {
+        transform.translation = interpolated.current;
+    });
+}
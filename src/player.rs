@@ -1,40 +1,77 @@
-use std::time::Duration;
+use std::{marker::PhantomData, time::Duration};
 
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::seq::SliceRandom;
 
 use crate::{
     cleanup::Dead,
-    game::{GameOverEvent, GameState, VIEW_BOX},
+    definitions::{SkinDefinitions, SoundSet},
+    difficulty::Difficulty,
+    game::{ElapsedTime, GameOverEvent, GameRng, GameState, VIEW_BOX},
+    input::Action,
+    mixer::{Bus, Sounds},
+    modes::GameMode,
     particles::ParticleEmitter,
-    physics::{FaceMovementDirection, Gravity, Movement, PlayerCollider},
+    physics::{FaceMovementDirection, Gravity, Movement, PlayerCollider, SIMULATION_STEP},
+    powerups::PowerUps,
+    replay::{Playback, RecordedInputs},
+    settings::Settings,
 };
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        let start = SystemSet::on_enter(GameState::Playing).with_system(make_player_sprite);
+        let start = SystemSet::on_enter(GameState::Playing)
+            .with_system(make_player_sprite.after("difficulty"));
         let update = SystemSet::on_update(GameState::Playing)
-            .with_system(jump_system.after("gravity").before("movement"))
-            .with_system(attack_state.before("collision"))
-            .with_system(player_out_of_bounds.after("movement"))
+            .with_system(read_jump_input.before("jump"))
             .with_system(animate_player);
         let end = SystemSet::on_exit(GameState::Playing).with_system(player_dead);
+        let tick = SystemSet::new()
+            .with_run_criteria("run_tick")
+            .with_system(
+                jump_system
+                    .label("jump")
+                    .after("gravity")
+                    .before("movement"),
+            )
+            .with_system(
+                attack_state
+                    .label("attack")
+                    .after("movement")
+                    .before("collision"),
+            )
+            .with_system(player_invulnerability.after("movement").before("collision"))
+            .with_system(
+                player_out_of_bounds
+                    .after("movement")
+                    .after("player_collision")
+                    .before("game_over"),
+            );
 
         let cleanup =
             SystemSet::on_exit(GameState::End).with_system(clean_player.before("cleanup"));
 
-        app.add_startup_system(load_assets)
+        app.init_resource::<PlayerInput>()
+            .insert_resource(SkinDefinitions::load())
+            .add_startup_system(load_assets)
+            .add_system(change_skin)
             .add_system_set(start)
             .add_system_set(update)
+            .add_system_set(tick)
             .add_system_set(end)
             .add_system_set(cleanup);
     }
 }
 
 pub const PLAYER_JUMP_STRENGTH: f32 = 500.;
-const PLAYER_SIZE_X: f32 = 169.0;
-const PLAYER_SIZE_Y: f32 = 169.0;
+pub const PLAYER_SIZE_X: f32 = 169.0;
+pub const PLAYER_SIZE_Y: f32 = 169.0;
+/// Size of the barbarian's collider when he isn't boosted
+pub const PLAYER_COLLIDER_SIZE: Vec2 = Vec2::new(PLAYER_SIZE_X * 0.9, PLAYER_SIZE_Y);
+/// Seconds the barbarian can't be hurt after losing a life
+pub const PLAYER_INVULNERABILITY: f32 = 2.0;
 
 #[derive(Default, Clone, PartialEq)]
 pub enum AttackState {
@@ -44,86 +81,236 @@ pub enum AttackState {
     SwingEnd,
 }
 
+impl AttackState {
+    /// The swing follows the jump, it lasts while the barbarian is still going up
+    pub fn from_velocity(y: f32, jump_strength: f32) -> Self {
+        let threshhold = jump_strength / 2.;
+        match y {
+            x if x > threshhold => AttackState::Swinging,
+            x if x > 0. => AttackState::SwingEnd,
+            _ => AttackState::NotAttacking,
+        }
+    }
+}
+
 #[derive(Resource)]
-struct PlayerAssets {
+pub struct PlayerAssets {
+    /// Name of the skin the assets are from
+    pub skin: String,
     state_normal: Handle<Image>,
     state_swing: Handle<Image>,
     state_swing_end: Handle<Image>,
     state_dead: Handle<Image>,
     axe: Handle<Image>,
     attack_sounds: Vec<Handle<AudioSource>>,
+    attack_volume: f32,
     death_sounds: Vec<Handle<AudioSource>>,
+    death_volume: f32,
+}
+
+/// Inputs collected since the last simulation tick, consumed by the next one
+#[derive(Resource, Default)]
+pub struct PlayerInput {
+    pub jump: bool,
+}
+
+impl PlayerAssets {
+    pub fn sprite(&self, state: &AttackState) -> Handle<Image> {
+        match state {
+            AttackState::NotAttacking => self.state_normal.clone(),
+            AttackState::Swinging => self.state_swing.clone(),
+            AttackState::SwingEnd => self.state_swing_end.clone(),
+        }
+    }
+
+    fn load(skins: &SkinDefinitions, name: Option<&str>, asset_server: &AssetServer) -> Self {
+        let skin = skins.get(name);
+        let load_sounds = |set: Option<&SoundSet>| match set {
+            Some(set) => (
+                set.files
+                    .iter()
+                    .map(|x| asset_server.load(x.as_str()))
+                    .collect(),
+                set.volume,
+            ),
+            None => (Vec::new(), 0.0),
+        };
+        let (attack_sounds, attack_volume) = load_sounds(skins.attack_sounds(skin));
+        let (death_sounds, death_volume) = load_sounds(skins.death_sounds(skin));
+        PlayerAssets {
+            skin: skin.name.clone(),
+            state_normal: asset_server.load(skin.falling.as_str()),
+            state_swing: asset_server.load(skin.swing.as_str()),
+            state_swing_end: asset_server.load(skin.chop.as_str()),
+            state_dead: asset_server.load(skin.dead.as_str()),
+            axe: asset_server.load(skin.axe.as_str()),
+            attack_sounds,
+            attack_volume,
+            death_sounds,
+            death_volume,
+        }
+    }
+
+    /// Sprite showing off the skin in the menu
+    pub fn preview(&self) -> Handle<Image> {
+        self.state_normal.clone()
+    }
 }
 
 #[derive(Component, Default)]
 pub struct Player {
     pub attack_state: AttackState,
+    pub lives: u32,
+    /// Seconds until the barbarian can be hurt again
+    invulnerable: f32,
+    /// Upwards speed of a jump, set by the difficulty
+    jump_strength: f32,
+}
+
+/// Settings the barbarian starts a run with
+#[derive(SystemParam)]
+struct RunSetup<'w, 's> {
+    settings: Res<'w, Settings>,
+    playback: Option<Res<'w, Playback>>,
+    difficulty: Res<'w, Difficulty>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl RunSetup<'_, '_> {
+    /// A replay is played back with the lives it was recorded with
+    fn lives(&self) -> u32 {
+        match &self.playback {
+            Some(playback) => playback.lives(),
+            None => self.settings.lives,
+        }
+    }
+}
+
+impl Player {
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable > 0.0
+    }
+
+    /// Takes a deadly hit, it costs a life unless a shield takes it.
+    /// The barbarian is invulnerable for a while after, false once he's out of lives.
+    pub fn hurt(&mut self, shielded: bool) -> bool {
+        if !shielded {
+            self.lives = self.lives.saturating_sub(1);
+        }
+        self.invulnerable = PLAYER_INVULNERABILITY;
+        self.lives > 0
+    }
 }
 
 #[derive(Component)]
 pub struct PlayerCorpse;
 
-fn load_assets(mut cmd: Commands, asset_server: Res<AssetServer>) {
-    let mut attack_sounds = Vec::new();
-    for i in 1..=19 {
-        attack_sounds.push(asset_server.load(format!("audio/barb-attack-{}.ogg", i)));
+fn load_assets(
+    mut cmd: Commands,
+    asset_server: Res<AssetServer>,
+    skins: Res<SkinDefinitions>,
+    settings: Res<Settings>,
+) {
+    let ass = PlayerAssets::load(&skins, settings.skin.as_deref(), &asset_server);
+    cmd.insert_resource(ass);
+}
+
+/// Swaps the assets out when another skin is picked
+fn change_skin(
+    mut assets: ResMut<PlayerAssets>,
+    asset_server: Res<AssetServer>,
+    skins: Res<SkinDefinitions>,
+    settings: Res<Settings>,
+) {
+    if !settings.is_changed() {
+        return;
     }
-    let mut death_sounds = Vec::new();
-    for i in 1..=8 {
-        death_sounds.push(asset_server.load(format!("audio/barb-death-{}.ogg", i)));
+    let name = settings.skin.as_deref();
+    if skins.get(name).name == assets.skin {
+        return;
+    }
+    *assets = PlayerAssets::load(&skins, name, &asset_server);
+}
+
+fn read_jump_input(
+    actions: Res<Input<Action>>,
+    mut input: ResMut<PlayerInput>,
+    playback: Option<Res<Playback>>,
+) {
+    // a replay feeds the recorded inputs instead
+    if playback.is_some() {
+        return;
+    }
+    if actions.just_pressed(Action::Jump) {
+        input.jump = true;
     }
-    let ass = PlayerAssets {
-        state_normal: asset_server.load("sprites/barbarian-falling.png"),
-        state_swing: asset_server.load("sprites/barbarian-midswing.png"),
-        state_swing_end: asset_server.load("sprites/barbarian-chop.png"),
-        state_dead: asset_server.load("sprites/barbarian-dead.png"),
-        axe: asset_server.load("sprites/axe.png"),
-        attack_sounds,
-        death_sounds,
-    };
-    cmd.insert_resource(ass);
 }
 
 fn jump_system(
-    input: Res<Input<KeyCode>>,
-    mut player: Query<&mut Movement, With<Player>>,
-    audio: Res<Audio>,
+    mut input: ResMut<PlayerInput>,
+    mut player: Query<(&mut Movement, &Player)>,
+    mut recorded: ResMut<RecordedInputs>,
+    elapsed: Res<ElapsedTime>,
+    sounds: Sounds,
     assets: Res<PlayerAssets>,
+    mut rng: ResMut<GameRng>,
 ) {
-    if input.just_pressed(KeyCode::Space) {
-        if let Ok(mut player) = player.get_single_mut() {
-            player.y = PLAYER_JUMP_STRENGTH;
-            play_attack_sound(&audio, &assets);
+    if std::mem::take(&mut input.jump) {
+        if let Ok((mut movement, player)) = player.get_single_mut() {
+            movement.y = player.jump_strength;
+            recorded.jumps.push(elapsed.ticks);
+            play_attack_sound(&sounds, &assets, &mut rng);
         }
     }
 }
 
 fn attack_state(mut player: Query<(&mut Player, &Movement)>) {
-    let mut pl = player.get_single_mut().unwrap();
-    let threshhold = PLAYER_JUMP_STRENGTH / 2.;
-    pl.0.attack_state = match pl.1.y {
-        x if x > threshhold => AttackState::Swinging,
-        x if x > 0. => AttackState::SwingEnd,
-        _ => AttackState::NotAttacking,
-    }
+    let Ok(mut pl) = player.get_single_mut() else {
+        return;
+    };
+    pl.0.attack_state = AttackState::from_velocity(pl.1.y, pl.0.jump_strength);
 }
 
-fn animate_player(mut cmd: Commands, player: Query<(Entity, &Player)>, assets: Res<PlayerAssets>) {
-    let pl = player.single();
-    let sprite = match pl.1.attack_state {
-        AttackState::NotAttacking => assets.state_normal.clone(),
-        AttackState::Swinging => assets.state_swing.clone(),
-        AttackState::SwingEnd => assets.state_swing_end.clone(),
-    };
-    cmd.entity(pl.0).insert(sprite);
+fn player_invulnerability(mut player: Query<&mut Player>) {
+    player.for_each_mut(|mut player| {
+        player.invulnerable = (player.invulnerable - SIMULATION_STEP).max(0.0);
+    });
+}
+
+fn animate_player(
+    mut cmd: Commands,
+    mut player: Query<(Entity, &Player, &mut Sprite)>,
+    assets: Res<PlayerAssets>,
+) {
+    let (entity, player, mut sprite) = player.single_mut();
+    cmd.entity(entity)
+        .insert(assets.sprite(&player.attack_state));
+    // blinks while invulnerable
+    let visible = (player.invulnerable * 10.0) as i32 % 2 == 0;
+    sprite.color.set_a(if visible { 1.0 } else { 0.3 });
+}
+
+/// Where the barbarian enters the screen at the start of a run
+pub fn player_start() -> Vec3 {
+    Vec3 {
+        x: VIEW_BOX.min.x + 256.0,
+        y: VIEW_BOX.min.y + VIEW_BOX.height() / 2.0,
+        ..default()
+    }
 }
 
 fn make_player_sprite(
     mut commands: Commands,
-    _asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
+    sounds: Sounds,
     assets: Res<PlayerAssets>,
+    setup: RunSetup,
+    mut recorded: ResMut<RecordedInputs>,
+    mut rng: ResMut<GameRng>,
 ) {
+    let lives = setup.lives();
+    let jump_strength = setup.difficulty.jump_strength();
+    recorded.lives = lives;
     commands.spawn((
         SpriteBundle {
             // texture: img,
@@ -134,37 +321,33 @@ fn make_player_sprite(
                 }),
                 ..Default::default()
             },
-            transform: Transform {
-                translation: Vec3 {
-                    x: VIEW_BOX.min.x + 256.0,
-                    y: VIEW_BOX.min.y + VIEW_BOX.height() / 2.0,
-                    ..default()
-                },
-                ..default()
-            },
+            transform: Transform::from_translation(player_start()),
             ..default()
         },
         Movement {
-            y: PLAYER_JUMP_STRENGTH,
+            y: jump_strength,
             ..Default::default()
         },
         PlayerCollider {
-            collision_size: Vec2 {
-                x: PLAYER_SIZE_X * 0.9,
-                y: PLAYER_SIZE_Y,
-            },
+            collision_size: PLAYER_COLLIDER_SIZE,
         },
         Gravity::default(),
-        Player::default(),
+        Player {
+            lives,
+            jump_strength,
+            ..default()
+        },
     ));
-    play_attack_sound(&audio, &assets);
+    commands.insert_resource(PlayerInput::default());
+    play_attack_sound(&sounds, &assets, &mut rng);
 }
 
 fn player_dead(
     mut player: Query<(&mut Movement, &Transform, Entity), With<Player>>,
     mut cmd: Commands,
     assets: Res<PlayerAssets>,
-    audio: Res<Audio>,
+    sounds: Sounds,
+    mut rng: ResMut<GameRng>,
 ) {
     let (mut movement, transform, entity) = player.single_mut();
     movement.y = PLAYER_JUMP_STRENGTH;
@@ -200,7 +383,7 @@ fn player_dead(
         Gravity::default(),
         Dead { timer: 5.0 },
     ));
-    play_death_sound(&audio, &assets);
+    play_death_sound(&sounds, &assets, &mut rng);
 }
 
 fn clean_player(mut cmd: Commands, player: Query<Entity, With<PlayerCorpse>>) {
@@ -211,32 +394,43 @@ fn clean_player(mut cmd: Commands, player: Query<Entity, With<PlayerCorpse>>) {
 
 fn player_out_of_bounds(
     mut event: EventWriter<GameOverEvent>,
-    player: Query<(&Transform, &Sprite), With<Player>>,
+    mut player: Query<(&mut Player, &mut Transform, &mut Movement, &Sprite)>,
+    mut power_ups: ResMut<PowerUps>,
+    mode: Res<GameMode>,
 ) {
-    let player = player.single();
-    let pos = player.0.translation.y;
-    let size = player.1.custom_size.unwrap().y / 2.0;
+    let (mut player, mut transform, mut movement, sprite) = player.single_mut();
+    let pos = transform.translation.y;
+    let size = sprite.custom_size.unwrap().y / 2.0;
     let bottom = pos - size;
     let top = pos + size;
     if bottom < VIEW_BOX.min.y || top > VIEW_BOX.max.y {
-        event.send_default();
+        // the shield is only spent on a hit that would cost a life
+        let survived = if player.is_invulnerable() {
+            true
+        } else {
+            let shielded = !mode.collisions_end_run() || power_ups.absorb_hit();
+            player.hurt(shielded)
+        };
+        // the barbarian comes back where the run started
+        if survived {
+            transform.translation = player_start();
+            movement.y = player.jump_strength;
+        } else {
+            event.send_default();
+        }
     }
 }
 
-fn play_attack_sound(audio: &Res<Audio>, assets: &PlayerAssets) {
-    let sound = assets
-        .attack_sounds
-        .get(rand::random::<usize>() % assets.attack_sounds.len())
-        .unwrap()
-        .clone();
-    audio.play(sound);
+fn play_attack_sound(sounds: &Sounds, assets: &PlayerAssets, rng: &mut GameRng) {
+    let Some(sound) = assets.attack_sounds.choose(&mut rng.cosmetic) else {
+        return;
+    };
+    sounds.play(sound.clone(), Bus::Voice, assets.attack_volume);
 }
 
-fn play_death_sound(audio: &Res<Audio>, assets: &PlayerAssets) {
-    let sound = assets
-        .death_sounds
-        .get(rand::random::<usize>() % assets.death_sounds.len())
-        .unwrap()
-        .clone();
-    audio.play(sound);
+fn play_death_sound(sounds: &Sounds, assets: &PlayerAssets, rng: &mut GameRng) {
+    let Some(sound) = assets.death_sounds.choose(&mut rng.cosmetic) else {
+        return;
+    };
+    sounds.play(sound.clone(), Bus::Voice, assets.death_volume);
 }
@@ -0,0 +1,274 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
+
+use crate::{
+    cleanup::Dead,
+    definitions::PowerUpDefinitions,
+    game::{FadeOut, GameRng, GameState, VIEW_BOX},
+    particles::ParticleEmitter,
+    physics::{
+        Movement, PlayerCollider, SimulationClock, SIMULATION_STEP, SIMULATION_STEP_DURATION,
+    },
+    player::{AttackState, Player, PLAYER_COLLIDER_SIZE},
+    ui::{Score, UiAssets},
+};
+
+/// Pickups that give the barbarian a timed boost
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        let start = SystemSet::on_enter(GameState::Playing).with_system(reset_power_ups);
+        let tick = SystemSet::new()
+            .with_run_criteria("run_tick")
+            .with_system(spawn_pickups.after("spawn").before("player_collision"))
+            .with_system(
+                collect_pickups
+                    .after("movement")
+                    .after(spawn_pickups)
+                    .before("player_collision"),
+            )
+            .with_system(
+                tick_power_ups
+                    .after(collect_pickups)
+                    .before("player_collision")
+                    .before("score"),
+            )
+            .with_system(big_axe.after("attack").before("collision"));
+        let cleanup = SystemSet::on_exit(GameState::End).with_system(cleanup_pickups);
+
+        app.insert_resource(PowerUpDefinitions::load())
+            .init_resource::<PowerUps>()
+            .add_system_set(start)
+            .add_system_set(tick)
+            .add_system_set(cleanup)
+            .add_system(slow_motion);
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum PowerUpKind {
+    /// Takes a deadly hit in place of a life
+    Shield,
+    ScoreMultiplier,
+    SlowMotion,
+    /// Bigger reach while swinging
+    BigAxe,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 4] = [
+        PowerUpKind::Shield,
+        PowerUpKind::ScoreMultiplier,
+        PowerUpKind::SlowMotion,
+        PowerUpKind::BigAxe,
+    ];
+}
+
+/// Seconds left of each effect
+#[derive(Resource, Default)]
+pub struct PowerUps {
+    shield: f32,
+    score_multiplier: f32,
+    slow_motion: f32,
+    big_axe: f32,
+}
+
+impl PowerUps {
+    pub fn remaining(&self, kind: PowerUpKind) -> f32 {
+        match kind {
+            PowerUpKind::Shield => self.shield,
+            PowerUpKind::ScoreMultiplier => self.score_multiplier,
+            PowerUpKind::SlowMotion => self.slow_motion,
+            PowerUpKind::BigAxe => self.big_axe,
+        }
+    }
+
+    fn remaining_mut(&mut self, kind: PowerUpKind) -> &mut f32 {
+        match kind {
+            PowerUpKind::Shield => &mut self.shield,
+            PowerUpKind::ScoreMultiplier => &mut self.score_multiplier,
+            PowerUpKind::SlowMotion => &mut self.slow_motion,
+            PowerUpKind::BigAxe => &mut self.big_axe,
+        }
+    }
+
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.remaining(kind) > 0.0
+    }
+
+    /// Uses the shield up on a deadly hit, true when there was one
+    pub fn absorb_hit(&mut self) -> bool {
+        std::mem::take(&mut self.shield) > 0.0
+    }
+}
+
+#[derive(Component)]
+struct Pickup {
+    kind: PowerUpKind,
+}
+
+/// Counts down to the next pickup
+#[derive(Resource, Deref, DerefMut)]
+struct PickupTimer(Timer);
+
+fn reset_power_ups(mut cmd: Commands, defs: Res<PowerUpDefinitions>) {
+    cmd.insert_resource(PowerUps::default());
+    cmd.insert_resource(PickupTimer(Timer::from_seconds(
+        defs.interval.0,
+        TimerMode::Once,
+    )));
+}
+
+fn spawn_pickups(
+    mut cmd: Commands,
+    mut timer: ResMut<PickupTimer>,
+    defs: Res<PowerUpDefinitions>,
+    ui: Res<UiAssets>,
+    score: Res<Score>,
+    mut rng: ResMut<GameRng>,
+) {
+    if score.score < defs.min_score || !timer.tick(SIMULATION_STEP_DURATION).just_finished() {
+        return;
+    }
+    let interval = rng.gameplay.gen_range(defs.interval.0..=defs.interval.1);
+    timer.set_duration(Duration::from_secs_f32(interval));
+    timer.reset();
+    let Ok(def) = defs
+        .power_ups
+        .choose_weighted(&mut rng.gameplay, |x| x.weight)
+    else {
+        return;
+    };
+    let band = rng.gameplay.gen_range(defs.height.0..=defs.height.1);
+    let height = (VIEW_BOX.min.y + VIEW_BOX.height() * band).clamp(
+        VIEW_BOX.min.y + defs.size.y / 2.,
+        VIEW_BOX.max.y - defs.size.y / 2.,
+    );
+    cmd.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(defs.size),
+                color: def.color,
+                ..default()
+            },
+            transform: Transform::from_xyz(VIEW_BOX.max.x + defs.size.x, height, 0.),
+            ..default()
+        },
+        Movement {
+            x: -defs.speed,
+            y: 0.,
+        },
+        Pickup { kind: def.kind },
+    ))
+    .with_children(|pickup| {
+        pickup.spawn(Text2dBundle {
+            text: Text::from_section(
+                def.label.clone(),
+                TextStyle {
+                    font: ui.font.clone(),
+                    font_size: 24.,
+                    color: Color::BLACK,
+                },
+            )
+            .with_alignment(TextAlignment::CENTER),
+            transform: Transform::from_xyz(0., 0., 0.1),
+            ..default()
+        });
+    });
+}
+
+fn collect_pickups(
+    mut cmd: Commands,
+    pickups: Query<(Entity, &Transform, &Sprite, &Pickup)>,
+    player: Query<(&Transform, &PlayerCollider), With<Player>>,
+    defs: Res<PowerUpDefinitions>,
+    mut power_ups: ResMut<PowerUps>,
+) {
+    let Ok((player, collider)) = player.get_single() else {
+        return;
+    };
+    pickups.for_each(|(entity, transform, sprite, pickup)| {
+        if transform.translation.x < VIEW_BOX.min.x - defs.size.x {
+            cmd.entity(entity)
+                .remove::<Pickup>()
+                .insert(Dead::default());
+            return;
+        }
+        let reach = (collider.collision_size + defs.size) / 2.;
+        let distance = (player.translation - transform.translation)
+            .truncate()
+            .abs();
+        if distance.x > reach.x || distance.y > reach.y {
+            return;
+        }
+        let Some(def) = defs.get(&pickup.kind) else {
+            return;
+        };
+        *power_ups.remaining_mut(pickup.kind) = def.duration;
+        cmd.entity(entity)
+            .remove::<Pickup>()
+            .remove::<Movement>()
+            .insert(FadeOut { speed: 4.0 })
+            .insert(Dead { timer: 0.25 });
+        cmd.spawn((
+            ParticleEmitter::new(5, Duration::new(0, 500), TimerMode::Repeating)
+                .with_color(sprite.color),
+            Transform::from_translation(transform.translation),
+            Dead { timer: 0.1 },
+        ));
+    });
+}
+
+fn tick_power_ups(
+    mut power_ups: ResMut<PowerUps>,
+    mut score: ResMut<Score>,
+    defs: Res<PowerUpDefinitions>,
+) {
+    for kind in PowerUpKind::ALL {
+        let remaining = power_ups.remaining_mut(kind);
+        *remaining = (*remaining - SIMULATION_STEP).max(0.0);
+    }
+    score.multiplier = if power_ups.is_active(PowerUpKind::ScoreMultiplier) {
+        defs.score_multiplier
+    } else {
+        1
+    };
+}
+
+fn big_axe(
+    mut player: Query<(&Player, &mut PlayerCollider)>,
+    power_ups: Res<PowerUps>,
+    defs: Res<PowerUpDefinitions>,
+) {
+    let Ok((player, mut collider)) = player.get_single_mut() else {
+        return;
+    };
+    let attacking = player.attack_state != AttackState::NotAttacking;
+    collider.collision_size = if attacking && power_ups.is_active(PowerUpKind::BigAxe) {
+        PLAYER_COLLIDER_SIZE * defs.big_axe
+    } else {
+        PLAYER_COLLIDER_SIZE
+    };
+}
+
+/// Slows the simulation clock down while slow motion lasts, only during a run
+fn slow_motion(
+    mut clock: ResMut<SimulationClock>,
+    power_ups: Res<PowerUps>,
+    defs: Res<PowerUpDefinitions>,
+    state: Res<State<GameState>>,
+) {
+    let slow =
+        *state.current() == GameState::Playing && power_ups.is_active(PowerUpKind::SlowMotion);
+    clock.time_scale = if slow { defs.slow_motion } else { 1.0 };
+}
+
+fn cleanup_pickups(mut cmd: Commands, pickups: Query<Entity, With<Pickup>>) {
+    pickups.for_each(|x| {
+        cmd.entity(x).remove::<Pickup>().insert(Dead::default());
+    });
+}
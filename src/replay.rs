@@ -0,0 +1,203 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    difficulty::Difficulty,
+    game::{ElapsedTime, GameOverEvent, GameRng, GameState},
+    modes::GameMode,
+    player::PlayerInput,
+    storage,
+    ui::Score,
+};
+
+const LAST_REPLAY_FILE: &str = "replays/last.ron";
+
+pub struct ReplayPlugin {
+    pub mode: ReplayMode,
+}
+
+pub enum ReplayMode {
+    Off,
+    /// Saves every finished run to a replay file
+    Record,
+    /// Plays the replay back instead of reading the keyboard
+    Playback(Replay),
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let start = SystemSet::on_enter(GameState::Playing).with_system(reset_replay);
+
+        app.init_resource::<RecordedInputs>().add_system_set(start);
+
+        match &self.mode {
+            ReplayMode::Off => {}
+            ReplayMode::Record => {
+                let end = SystemSet::on_enter(GameState::End).with_system(save_replay);
+                app.add_system_set(end);
+            }
+            ReplayMode::Playback(replay) => {
+                let tick = SystemSet::new()
+                    .with_run_criteria("run_tick")
+                    .with_system(feed_replay_input.before("jump"))
+                    .with_system(end_replay.before("game_over"));
+                let end = SystemSet::on_enter(GameState::End).with_system(verify_replay);
+                app.insert_resource(Playback {
+                    replay: replay.clone(),
+                    next: 0,
+                })
+                .add_system_set(tick)
+                .add_system_set(end);
+            }
+        }
+    }
+}
+
+/// Everything needed to play a run again: the seed it was played with and the ticks the player jumped on
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub jumps: Vec<u32>,
+    /// Length of the run in simulation ticks
+    #[serde(default)]
+    pub ticks: u32,
+    /// Final score of the recorded run, a replay that doesn't reach it is out of sync
+    pub score: i32,
+    pub max_combo: i32,
+    /// Lives the run started with, runs before lives were added had one
+    #[serde(default = "one_life")]
+    pub lives: u32,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    #[serde(default)]
+    pub mode: GameMode,
+}
+
+fn one_life() -> u32 {
+    1
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Option<Self> {
+        storage::load_path(path)
+    }
+
+    /// Replay of the best run recorded so far on the difficulty and in the mode
+    pub fn best(difficulty: Difficulty, mode: GameMode) -> Option<Self> {
+        storage::load(&best_replay_file(difficulty, mode))
+    }
+}
+
+/// Normal Endless runs keep the file from before there were presets and modes
+fn best_replay_file(difficulty: Difficulty, mode: GameMode) -> String {
+    let mut file = String::from("replays/best");
+    if difficulty != Difficulty::Normal {
+        file += &format!("-{}", difficulty.label().to_lowercase());
+    }
+    if mode != GameMode::Endless {
+        file += &format!("-{}", mode.label().to_lowercase().replace(' ', "-"));
+    }
+    file + ".ron"
+}
+
+/// Jumps of the current run, by the simulation tick they happened on
+#[derive(Resource, Default)]
+pub struct RecordedInputs {
+    pub jumps: Vec<u32>,
+    /// Lives the run started with
+    pub lives: u32,
+    pub difficulty: Difficulty,
+    pub mode: GameMode,
+}
+
+/// The replay being played back and the next jump in it
+#[derive(Resource)]
+pub struct Playback {
+    replay: Replay,
+    next: usize,
+}
+
+impl Playback {
+    pub fn lives(&self) -> u32 {
+        self.replay.lives
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.replay.difficulty
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.replay.mode
+    }
+}
+
+fn reset_replay(mut recorded: ResMut<RecordedInputs>, playback: Option<ResMut<Playback>>) {
+    recorded.jumps.clear();
+    if let Some(mut playback) = playback {
+        playback.next = 0;
+    }
+}
+
+fn feed_replay_input(
+    mut playback: ResMut<Playback>,
+    mut input: ResMut<PlayerInput>,
+    elapsed: Res<ElapsedTime>,
+) {
+    let next = playback.next;
+    if playback.replay.jumps.get(next) == Some(&elapsed.ticks) {
+        input.jump = true;
+        playback.next += 1;
+    }
+}
+
+/// Ends the run on the tick the recording ended, runs the player ended by hand have nothing else ending them
+fn end_replay(
+    playback: Res<Playback>,
+    elapsed: Res<ElapsedTime>,
+    mut game_over: EventWriter<GameOverEvent>,
+) {
+    let ticks = playback.replay.ticks;
+    if ticks > 0 && elapsed.ticks + 1 >= ticks {
+        game_over.send_default();
+    }
+}
+
+fn save_replay(
+    recorded: Res<RecordedInputs>,
+    elapsed: Res<ElapsedTime>,
+    score: Res<Score>,
+    rng: Res<GameRng>,
+) {
+    let replay = Replay {
+        seed: rng.seed,
+        jumps: recorded.jumps.clone(),
+        ticks: elapsed.ticks,
+        score: score.score,
+        max_combo: score.max_combo,
+        lives: recorded.lives,
+        difficulty: recorded.difficulty,
+        mode: recorded.mode,
+    };
+    storage::save(LAST_REPLAY_FILE, &replay);
+    let is_best = match Replay::best(replay.difficulty, replay.mode) {
+        Some(best) => replay.score > best.score,
+        None => true,
+    };
+    if is_best {
+        storage::save(&best_replay_file(replay.difficulty, replay.mode), &replay);
+    }
+}
+
+fn verify_replay(playback: Res<Playback>, score: Res<Score>) {
+    let replay = &playback.replay;
+    if (score.score, score.max_combo) == (replay.score, replay.max_combo) {
+        println!("Replay verified, score: {}", score.score);
+    } else {
+        println!(
+            "Replay out of sync, recorded score: {} max combo: {}, replayed score: {} max combo: {}",
+            replay.score, replay.max_combo, score.score, score.max_combo
+        );
+    }
+}
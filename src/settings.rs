@@ -0,0 +1,114 @@
+use bevy::{prelude::*, window::WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    difficulty::Difficulty,
+    game::ScreenShake,
+    input::InputBindings,
+    mixer::{AudioMixer, Bus},
+    modes::GameMode,
+    particles::ParticleDensity,
+    storage,
+};
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+/// Loads the settings at startup, applies them to the game and writes them back whenever they change
+pub struct SettingsPlugin {
+    /// Uses the defaults instead of the saved settings, so simulated runs play the same on every machine
+    pub defaults: bool,
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = if self.defaults {
+            Settings::default()
+        } else {
+            Settings::load()
+        };
+        app.insert_resource(settings)
+            .add_system(apply_settings)
+            .add_system(save_bindings);
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub voice_volume: f32,
+    pub music_volume: f32,
+    pub screen_shake: f32,
+    pub particle_density: f32,
+    pub fullscreen: bool,
+    /// Lives the barbarian starts a run with
+    pub lives: u32,
+    /// Name of the barbarian skin, the first one in the manifest when unset
+    pub skin: Option<String>,
+    pub difficulty: Difficulty,
+    pub mode: GameMode,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            // the shouts are recorded much louder than the rest
+            voice_volume: 0.5,
+            music_volume: 1.0,
+            screen_shake: 1.0,
+            particle_density: 1.0,
+            fullscreen: false,
+            lives: 3,
+            skin: None,
+            difficulty: Difficulty::Normal,
+            mode: GameMode::Endless,
+        }
+    }
+}
+
+impl Settings {
+    fn load() -> Self {
+        storage::load(SETTINGS_FILE).unwrap_or_default()
+    }
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut shake: ResMut<ScreenShake>,
+    mut density: ResMut<ParticleDensity>,
+    mut mixer: ResMut<AudioMixer>,
+    mut windows: ResMut<Windows>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    // settings loaded from the file don't need to be written back
+    if !settings.is_added() {
+        storage::save(SETTINGS_FILE, &*settings);
+    }
+    mixer.set(Bus::Master, settings.master_volume);
+    mixer.set(Bus::Sfx, settings.sfx_volume);
+    mixer.set(Bus::Voice, settings.voice_volume);
+    mixer.set(Bus::Music, settings.music_volume);
+    shake.intensity = settings.screen_shake;
+    density.0 = settings.particle_density;
+    if let Some(window) = windows.get_primary_mut() {
+        let mode = if settings.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        };
+        if window.mode() != mode {
+            window.set_mode(mode);
+        }
+    }
+}
+
+fn save_bindings(bindings: Res<InputBindings>) {
+    if bindings.is_changed() && !bindings.is_added() {
+        bindings.save();
+    }
+}
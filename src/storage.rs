@@ -0,0 +1,73 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Folder the game's assets are installed in
+pub fn asset_folder() -> String {
+    if cfg!(feature = "local_unix_assets") {
+        let home = match std::env::var("HOME") {
+            Ok(o) => o,
+            Err(_) => panic!("Couldn't obtain HOME directory"),
+        };
+        format!("{}/.local/share/flappy-barb/", home)
+    } else if cfg!(feature = "unix_assets") {
+        String::from("/usr/share/flappy-barb/")
+    } else {
+        String::from("assets/")
+    }
+}
+
+/// Folder where the game keeps its save files, follows the same split as the asset folder
+pub fn data_folder() -> PathBuf {
+    if cfg!(any(feature = "local_unix_assets", feature = "unix_assets")) {
+        let home = match std::env::var("HOME") {
+            Ok(o) => o,
+            Err(_) => panic!("Couldn't obtain HOME directory"),
+        };
+        PathBuf::from(format!("{}/.local/share/flappy-barb/", home))
+    } else {
+        PathBuf::from("data/")
+    }
+}
+
+/// Reads a save file from the data folder, returns None if it doesn't exist or is malformed
+pub fn load<T: DeserializeOwned>(file: &str) -> Option<T> {
+    load_path(&data_folder().join(file))
+}
+
+/// Reads a file anywhere on disk in the save file format
+pub fn load_path<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return None;
+    };
+    match ron::from_str(&content) {
+        Ok(o) => Some(o),
+        Err(e) => {
+            println!("Error: couldn't parse {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Writes a save file to the data folder, creating the folders on its path if needed
+pub fn save<T: Serialize>(file: &str, value: &T) {
+    let path = data_folder().join(file);
+    let folder = path.parent().unwrap_or(&path);
+    if let Err(e) = fs::create_dir_all(folder) {
+        println!("Error: couldn't create {}: {e}", folder.display());
+        return;
+    }
+    let content = match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(o) => o,
+        Err(e) => {
+            println!("Error: {e}");
+            return;
+        }
+    };
+    if let Err(e) = fs::write(&path, content) {
+        println!("Error: couldn't write {}: {e}", path.display());
+    }
+}
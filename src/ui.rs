@@ -1,44 +1,162 @@
 use bevy::prelude::*;
 
-use crate::{cleanup::Dead, game::GameState};
+use crate::{
+    cleanup::Dead,
+    coins::{Wallet, SHOP_ITEMS},
+    definitions::PowerUpDefinitions,
+    definitions::SkinDefinitions,
+    difficulty::Difficulty,
+    game::{not_paused, ElapsedTime, GameState},
+    highscores::{HighScores, ScoreEntry},
+    input::{Action, Devices, InputBindings},
+    modes::GameMode,
+    obstacles::{Boss, Health, ObstacleKind},
+    physics::SIMULATION_STEP,
+    player::{Player, PlayerAssets},
+    powerups::PowerUps,
+    settings::Settings,
+};
+
+/// Seconds the combo lasts without a kill
+const COMBO_TIME: f32 = 4.0;
+/// Combo needed for each multiplier tier, the first one is x2
+const COMBO_TIERS: [i32; 4] = [10, 25, 50, 100];
 
 pub struct GameUiPlugin;
 
 impl Plugin for GameUiPlugin {
     fn build(&self, app: &mut App) {
         let start_menu = SystemSet::on_enter(GameState::MainMenu).with_system(spawn_main_manu);
-        let update_menu = SystemSet::on_update(GameState::MainMenu).with_system(main_menu);
+        let update_menu = SystemSet::on_update(GameState::MainMenu)
+            .with_system(main_menu)
+            .with_system(update_skin_picker.after(main_menu))
+            .with_system(update_difficulty_picker.after(main_menu))
+            .with_system(update_mode_picker.after(main_menu));
         let exit_menu = SystemSet::on_exit(GameState::MainMenu).with_system(clean_ui);
 
+        let start_settings =
+            SystemSet::on_enter(GameState::Settings).with_system(spawn_settings_menu);
+        let update_settings = SystemSet::on_update(GameState::Settings)
+            .with_system(capture_binding.before(settings_menu))
+            .with_system(settings_menu)
+            .with_system(update_settings_text.after(settings_menu));
+        let exit_settings = SystemSet::on_exit(GameState::Settings).with_system(clean_ui);
+
+        let start_shop = SystemSet::on_enter(GameState::Shop).with_system(spawn_shop);
+        let update_shop = SystemSet::on_update(GameState::Shop)
+            .with_system(shop_menu)
+            .with_system(update_shop_text.after(shop_menu));
+        let exit_shop = SystemSet::on_exit(GameState::Shop).with_system(clean_ui);
+
         let start_game = SystemSet::on_enter(GameState::Playing).with_system(spawn_scoreboard);
-        let update_game = SystemSet::on_update(GameState::Playing).with_system(score_event);
+        let update_game = SystemSet::on_update(GameState::Playing)
+            .with_system(update_scoreboard)
+            .with_system(update_boss_health)
+            .with_system(update_combo_meter)
+            .with_system(update_power_up_hud);
+        let tick_game = SystemSet::new()
+            .with_run_criteria("run_tick")
+            .with_system(combo_decay.before("score"))
+            .with_system(score_event.label("score").before("game_over"));
         let exit_game = SystemSet::on_exit(GameState::Playing).with_system(clean_ui);
+        let callouts = SystemSet::new()
+            .with_run_criteria(not_paused)
+            .with_system(spawn_score_popups)
+            .with_system(animate_callouts);
+
+        let start_pause = SystemSet::on_enter(GameState::Paused).with_system(spawn_pause_menu);
+        let update_pause = SystemSet::on_update(GameState::Paused).with_system(pause_menu);
+        let exit_pause = SystemSet::on_exit(GameState::Paused).with_system(clean_pause_menu);
 
-        let start_end = SystemSet::on_enter(GameState::End).with_system(spawn_end_score);
+        let start_end =
+            SystemSet::on_enter(GameState::End).with_system(spawn_end_score.after("record_score"));
         let update_end = SystemSet::on_update(GameState::End).with_system(end_screen_menu);
         let exit_end = SystemSet::on_exit(GameState::End).with_system(clean_ui);
 
         app.add_event::<ScoreEvent>()
+            .add_event::<ScoreChange>()
             .insert_resource(Score::default())
+            .init_resource::<Rebinding>()
             .add_startup_system(load_font)
             .add_system_set(start_menu)
             .add_system_set(update_menu)
             .add_system_set(exit_menu)
+            .add_system_set(start_settings)
+            .add_system_set(update_settings)
+            .add_system_set(exit_settings)
+            .add_system_set(start_shop)
+            .add_system_set(update_shop)
+            .add_system_set(exit_shop)
             .add_system_set(start_game)
             .add_system_set(update_game)
+            .add_system_set(tick_game)
             .add_system_set(exit_game)
+            .add_system_set(callouts)
+            .add_system_set(start_pause)
+            .add_system_set(update_pause)
+            .add_system_set(exit_pause)
             .add_system_set(start_end)
             .add_system_set(update_end)
             .add_system_set(exit_end);
     }
 }
 
+/// Changes to the score, sent once the combo and the multiplier are applied
 #[derive(PartialEq)]
 pub enum ScoreEvent {
-    Add,
+    Add {
+        points: i32,
+        /// Where in the world the points were earned
+        position: Vec3,
+        source: ScoreSource,
+        kind: ObstacleKind,
+    },
     ResetCombo,
 }
 
+/// Kills and lost combos reported by the game, scored into ScoreEvents
+pub enum ScoreChange {
+    Kill {
+        position: Vec3,
+        source: ScoreSource,
+        kind: ObstacleKind,
+    },
+    ResetCombo,
+}
+
+/// What the points were given for
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ScoreSource {
+    /// Chopped by the barbarian
+    Melee,
+    /// Hit by the corpse of a chopped obstacle
+    Projectile,
+    /// Hit by the corpse of a corpse kill, with the number of kills in the chain
+    Chain(u32),
+    /// Combo bonus on top of a kill
+    Bonus,
+}
+
+impl ScoreSource {
+    /// Source of a kill that's the given link of a chain, the barbarian's own kill is the first
+    pub fn from_chain(links: u32) -> Self {
+        match links {
+            0 | 1 => ScoreSource::Melee,
+            2 => ScoreSource::Projectile,
+            x => ScoreSource::Chain(x),
+        }
+    }
+
+    /// Kills in the chain that led to the points
+    pub fn links(&self) -> u32 {
+        match self {
+            ScoreSource::Melee | ScoreSource::Bonus => 1,
+            ScoreSource::Projectile => 2,
+            ScoreSource::Chain(x) => *x,
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct UI;
 
@@ -50,24 +168,290 @@ pub struct UiAssets {
 #[derive(Component)]
 pub struct ScoreBoard;
 
-#[derive(Default, Resource)]
+/// Combo multiplier tier shown next to the combo meter
+#[derive(Component)]
+struct ComboTier;
+
+/// Part of the combo meter showing the time left before the combo drops
+#[derive(Component)]
+struct ComboMeterFill;
+
+/// Frame of the boss health bar, hidden while there's no boss
+#[derive(Component)]
+struct BossHealthBar;
+
+/// Part of the boss health bar showing the health left
+#[derive(Component)]
+struct BossHealthFill;
+
+/// Lists the active power-ups and their time left
+#[derive(Component)]
+struct PowerUpHud;
+
+/// Text popping up in the world, drifts up while it fades
+#[derive(Component)]
+struct Callout {
+    timer: f32,
+}
+
+/// Buttons whose interaction state changed this frame
+type ButtonInteractions<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Interaction, &'static mut BackgroundColor),
+    (Changed<Interaction>, With<Button>),
+>;
+
+/// Main menu buttons whose interaction state changed this frame
+type MenuButtons<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Interaction,
+        &'static mut BackgroundColor,
+        &'static MenuButton,
+    ),
+    (Changed<Interaction>, With<Button>),
+>;
+
+/// Settings screen buttons whose interaction state changed this frame
+type SettingsButtons<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Interaction,
+        &'static mut BackgroundColor,
+        &'static SettingsButton,
+    ),
+    (Changed<Interaction>, With<Button>),
+>;
+
+/// Pause menu buttons whose interaction state changed this frame
+type PauseButtons<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Interaction,
+        &'static mut BackgroundColor,
+        &'static PauseButton,
+    ),
+    (Changed<Interaction>, With<Button>),
+>;
+
+/// Shop buttons whose interaction state changed this frame
+type ShopButtons<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Interaction,
+        &'static mut BackgroundColor,
+        &'static ShopButton,
+    ),
+    (Changed<Interaction>, With<Button>),
+>;
+
+#[derive(Component)]
+enum MenuButton {
+    Start,
+    Settings,
+    Shop,
+    PreviousSkin,
+    NextSkin,
+    PreviousDifficulty,
+    NextDifficulty,
+    PreviousMode,
+    NextMode,
+}
+
+#[derive(Component)]
+enum PauseButton {
+    Resume,
+    EndRun,
+}
+
+/// Name of the picked difficulty in the main menu
+#[derive(Component)]
+struct DifficultyName;
+
+/// Name of the picked mode in the main menu
+#[derive(Component)]
+struct ModeName;
+
+/// Name of the picked skin in the main menu
+#[derive(Component)]
+struct SkinName;
+
+/// Picture of the picked skin in the main menu
+#[derive(Component)]
+struct SkinPreview;
+
+/// Values adjustable on the settings screen
+#[derive(Clone, Copy, PartialEq)]
+enum Setting {
+    MasterVolume,
+    SfxVolume,
+    VoiceVolume,
+    MusicVolume,
+    ScreenShake,
+    ParticleDensity,
+    Fullscreen,
+    Lives,
+}
+
+impl Setting {
+    const ALL: [Setting; 8] = [
+        Setting::MasterVolume,
+        Setting::SfxVolume,
+        Setting::VoiceVolume,
+        Setting::MusicVolume,
+        Setting::ScreenShake,
+        Setting::ParticleDensity,
+        Setting::Fullscreen,
+        Setting::Lives,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Setting::MasterVolume => "Master Volume",
+            Setting::SfxVolume => "Effects Volume",
+            Setting::VoiceVolume => "Voice Volume",
+            Setting::MusicVolume => "Music Volume",
+            Setting::ScreenShake => "Screen Shake",
+            Setting::ParticleDensity => "Particles",
+            Setting::Fullscreen => "Fullscreen",
+            Setting::Lives => "Lives",
+        }
+    }
+
+    /// The value, its step and its upper limit, the lower limit is always 0
+    fn value<'a>(&self, settings: &'a mut Settings) -> Option<(&'a mut f32, f32, f32)> {
+        match self {
+            Setting::MasterVolume => Some((&mut settings.master_volume, 0.1, 1.0)),
+            Setting::SfxVolume => Some((&mut settings.sfx_volume, 0.1, 1.0)),
+            Setting::VoiceVolume => Some((&mut settings.voice_volume, 0.1, 1.0)),
+            Setting::MusicVolume => Some((&mut settings.music_volume, 0.1, 1.0)),
+            Setting::ScreenShake => Some((&mut settings.screen_shake, 0.25, 2.0)),
+            Setting::ParticleDensity => Some((&mut settings.particle_density, 0.25, 1.0)),
+            Setting::Fullscreen | Setting::Lives => None,
+        }
+    }
+
+    /// Moves the value by the amount of steps, toggles the switches
+    fn adjust(&self, settings: &mut Settings, steps: f32) {
+        match self.value(settings) {
+            Some((value, step, max)) => {
+                // rounding to the step keeps repeated float additions from drifting
+                *value = ((*value / step).round() + steps).clamp(0.0, max / step) * step;
+            }
+            None if *self == Setting::Lives => {
+                settings.lives = (settings.lives as f32 + steps).clamp(1.0, 9.0) as u32;
+            }
+            None => settings.fullscreen = !settings.fullscreen,
+        }
+    }
+
+    fn text(&self, settings: &Settings) -> String {
+        match self {
+            Setting::MasterVolume => format!("{:.0}%", settings.master_volume * 100.0),
+            Setting::SfxVolume => format!("{:.0}%", settings.sfx_volume * 100.0),
+            Setting::VoiceVolume => format!("{:.0}%", settings.voice_volume * 100.0),
+            Setting::MusicVolume => format!("{:.0}%", settings.music_volume * 100.0),
+            Setting::ScreenShake => format!("{:.0}%", settings.screen_shake * 100.0),
+            Setting::ParticleDensity => format!("{:.0}%", settings.particle_density * 100.0),
+            Setting::Fullscreen => String::from(if settings.fullscreen { "On" } else { "Off" }),
+            Setting::Lives => settings.lives.to_string(),
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+enum SettingsButton {
+    Decrease(Setting),
+    Increase(Setting),
+    Rebind(Action),
+    Back,
+}
+
+#[derive(Component, Clone, Copy)]
+enum ShopButton {
+    /// Buys the item at this index of the shop, or equips it when it's owned
+    Item(usize),
+    Back,
+}
+
+/// Price or state of the shop item at this index
+#[derive(Component)]
+struct ShopItemText(usize);
+
+#[derive(Component)]
+struct CoinBalance;
+
+#[derive(Component)]
+struct SettingText(Setting);
+
+#[derive(Component)]
+struct BindingText(Action);
+
+/// Action waiting for a button to be bound to it
+#[derive(Resource, Default)]
+struct Rebinding(Option<Action>);
+
+/// Marks the pause overlay, kept separate from UI so the scoreboard survives unpausing
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Resource)]
 pub struct Score {
     pub score: i32,
     pub current_combo: i32,
     pub max_combo: i32,
+    /// Points are multiplied by this, raised by the score multiplier power-up
+    pub multiplier: i32,
+    /// Seconds left to make a kill before the combo drops
+    pub combo_timer: f32,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            score: 0,
+            current_combo: 0,
+            max_combo: 0,
+            multiplier: 1,
+            combo_timer: 0.0,
+        }
+    }
 }
 
 impl Score {
-    pub fn add_to_score(&mut self) {
-        let combo_bonus = self.current_combo / 10;
-        self.score += 1 + combo_bonus;
+    /// Adds a kill and returns the points and the combo bonus it earned.
+    /// Chain kills are worth as many times more as there are kills in the chain.
+    pub fn add_to_score(&mut self, chain: u32, combo_bonus: bool) -> (i32, i32) {
+        let points = self.multiplier * chain as i32;
+        let bonus = if combo_bonus {
+            (self.tier() - 1) * points
+        } else {
+            0
+        };
+        self.combo_timer = COMBO_TIME;
+        self.score += points + bonus;
         self.current_combo += 1;
         if self.current_combo > self.max_combo {
             self.max_combo = self.current_combo;
         }
+        (points, bonus)
     }
     pub fn reset_combo(&mut self) {
         self.current_combo = 0;
+        self.combo_timer = 0.0;
+    }
+
+    /// Multiplier earned by the current combo, one more for every tier reached
+    pub fn tier(&self) -> i32 {
+        1 + COMBO_TIERS
+            .iter()
+            .filter(|x| self.current_combo >= **x)
+            .count() as i32
     }
 }
 
@@ -80,18 +464,70 @@ fn load_font(mut cmd: Commands, asset_server: Res<AssetServer>) {
 
 fn score_event(
     mut score: ResMut<Score>,
-    mut ev: EventReader<ScoreEvent>,
-    mut board: Query<&mut Text, With<ScoreBoard>>,
+    mut changes: EventReader<ScoreChange>,
+    mut ev: EventWriter<ScoreEvent>,
+    mode: Res<GameMode>,
 ) {
-    ev.iter().for_each(|e| match e {
-        ScoreEvent::Add => score.add_to_score(),
-        ScoreEvent::ResetCombo => score.reset_combo(),
+    changes.iter().for_each(|change| match change {
+        ScoreChange::Kill {
+            position,
+            source,
+            kind,
+        } => {
+            let (points, bonus) = score.add_to_score(source.links(), mode.combo_bonus());
+            ev.send(ScoreEvent::Add {
+                points,
+                position: *position,
+                source: *source,
+                kind: kind.clone(),
+            });
+            if bonus > 0 {
+                ev.send(ScoreEvent::Add {
+                    points: bonus,
+                    position: *position,
+                    source: ScoreSource::Bonus,
+                    kind: kind.clone(),
+                });
+            }
+        }
+        ScoreChange::ResetCombo => {
+            score.reset_combo();
+            ev.send(ScoreEvent::ResetCombo);
+        }
     });
+}
+
+/// Drops the combo once too long passes without a kill
+fn combo_decay(mut score: ResMut<Score>, mut changes: EventWriter<ScoreChange>) {
+    if score.current_combo == 0 {
+        return;
+    }
+    score.combo_timer -= SIMULATION_STEP;
+    if score.combo_timer <= 0.0 {
+        changes.send(ScoreChange::ResetCombo);
+    }
+}
 
+fn update_scoreboard(
+    score: Res<Score>,
+    wallet: Res<Wallet>,
+    player: Query<&Player>,
+    mut board: Query<&mut Text, With<ScoreBoard>>,
+    mode: Res<GameMode>,
+    elapsed: Res<ElapsedTime>,
+) {
     let Ok(mut board) = board.get_single_mut() else {
         return;
     };
-    board.sections[0].value = format!("Score: {}\nCombo: {}", score.score, score.current_combo);
+    let lives = player.get_single().map_or(0, |x| x.lives);
+    let mut text = format!(
+        "Score: {}\nCombo: {}\nLives: {}\nCoins: {}",
+        score.score, score.current_combo, lives, wallet.coins
+    );
+    if let Some(limit) = mode.time_limit() {
+        text += &format!("\nTime: {:.0}", (limit - elapsed.time).max(0.0).ceil());
+    }
+    board.sections[0].value = text;
 }
 
 fn spawn_scoreboard(mut cmd: Commands, ui: Res<UiAssets>) {
@@ -102,60 +538,826 @@ fn spawn_scoreboard(mut cmd: Commands, ui: Res<UiAssets>) {
             font_size: 50.,
             color: Color::WHITE,
         },
+    );
+
+    cmd.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.),
+                    top: Val::Px(10.),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::Column,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        UI,
+    ))
+    .with_children(|parent| {
+        parent.spawn((board, ScoreBoard));
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with_children(|meter| {
+                meter.spawn((
+                    TextBundle::from_section(
+                        "x1",
+                        TextStyle {
+                            font: ui.font.clone(),
+                            font_size: 40.,
+                            color: Color::GOLD,
+                        },
+                    )
+                    .with_style(Style {
+                        size: Size::new(Val::Px(50.), Val::Auto),
+                        ..Default::default()
+                    }),
+                    ComboTier,
+                ));
+                meter
+                    .spawn(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(200.), Val::Px(16.)),
+                            padding: UiRect::all(Val::Px(3.)),
+                            ..Default::default()
+                        },
+                        background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                        ..Default::default()
+                    })
+                    .with_children(|bar| {
+                        bar.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                                    ..Default::default()
+                                },
+                                background_color: Color::GOLD.into(),
+                                ..Default::default()
+                            },
+                            ComboMeterFill,
+                        ));
+                    });
+            });
+    });
+    cmd.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(30.),
+                    top: Val::Px(20.),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Percent(40.), Val::Px(24.)),
+                padding: UiRect::all(Val::Px(4.)),
+                ..Default::default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.6).into(),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        },
+        BossHealthBar,
+        UI,
+    ))
+    .with_children(|bar| {
+        bar.spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    ..Default::default()
+                },
+                background_color: Color::RED.into(),
+                ..Default::default()
+            },
+            BossHealthFill,
+        ));
+    });
+    let hud = TextBundle::from_section(
+        "",
+        TextStyle {
+            font: ui.font.clone(),
+            font_size: 40.,
+            color: Color::WHITE,
+        },
     )
     .with_style(Style {
         position_type: PositionType::Absolute,
         position: UiRect {
-            left: Val::Px(10.),
+            right: Val::Px(10.),
             top: Val::Px(10.),
             ..Default::default()
         },
         ..Default::default()
+    })
+    .with_text_alignment(TextAlignment::TOP_RIGHT);
+    cmd.spawn((hud, PowerUpHud, UI));
+    cmd.insert_resource(Score::default());
+}
+
+fn update_power_up_hud(
+    power_ups: Res<PowerUps>,
+    defs: Res<PowerUpDefinitions>,
+    mut hud: Query<&mut Text, With<PowerUpHud>>,
+) {
+    let Ok(mut hud) = hud.get_single_mut() else {
+        return;
+    };
+    hud.sections[0].value = defs
+        .power_ups
+        .iter()
+        .filter(|x| power_ups.is_active(x.kind))
+        .map(|x| format!("{} {:.1}", x.label, power_ups.remaining(x.kind)))
+        .collect::<Vec<_>>()
+        .join("\n");
+}
+
+fn update_combo_meter(
+    score: Res<Score>,
+    mut tier: Query<&mut Text, With<ComboTier>>,
+    mut fill: Query<&mut Style, With<ComboMeterFill>>,
+) {
+    tier.for_each_mut(|mut x| x.sections[0].value = format!("x{}", score.tier()));
+    fill.for_each_mut(|mut x| {
+        x.size.width = Val::Percent(100. * score.combo_timer / COMBO_TIME);
     });
+}
 
-    cmd.spawn((board, ScoreBoard, UI));
-    cmd.insert_resource(Score::default());
+fn update_boss_health(
+    bosses: Query<&Health, With<Boss>>,
+    mut bar: Query<&mut Visibility, With<BossHealthBar>>,
+    mut fill: Query<&mut Style, With<BossHealthFill>>,
+) {
+    let boss = bosses.get_single().ok();
+    bar.for_each_mut(|mut x| x.is_visible = boss.is_some());
+    let Some(boss) = boss else {
+        return;
+    };
+    fill.for_each_mut(|mut x| {
+        x.size.width = Val::Percent(100. * boss.current as f32 / boss.max as f32);
+    });
+}
+
+/// Floating "+N" where points were earned, chain kills also call out the chain
+fn spawn_score_popups(mut cmd: Commands, mut ev: EventReader<ScoreEvent>, ui: Res<UiAssets>) {
+    let mut callout = |text: String, position: Vec3, font_size: f32, color: Color| {
+        cmd.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    text,
+                    TextStyle {
+                        font: ui.font.clone(),
+                        font_size,
+                        color,
+                    },
+                )
+                .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            Callout { timer: 1.0 },
+            Dead { timer: 1.0 },
+        ));
+    };
+    ev.iter().for_each(|e| {
+        let ScoreEvent::Add {
+            points,
+            position,
+            source,
+            ..
+        } = e
+        else {
+            return;
+        };
+        let position = Vec3::new(position.x, position.y, 10.);
+        match source {
+            // the bonus shows right above the points of its kill
+            ScoreSource::Bonus => callout(
+                format!("+{points} combo"),
+                position + Vec3::Y * 35.,
+                30.,
+                Color::ORANGE,
+            ),
+            _ => callout(format!("+{points}"), position, 40., Color::WHITE),
+        }
+        let links = source.links();
+        if links > 1 {
+            callout(
+                format!("CHAIN x{links}"),
+                position + Vec3::Y * 80.,
+                // longer chains shout louder
+                40. + 8. * links.min(6) as f32,
+                Color::GOLD,
+            );
+        }
+    });
 }
 
-fn spawn_main_manu(mut cmd: Commands, ui: Res<UiAssets>) {
+fn animate_callouts(
+    mut callouts: Query<(&mut Transform, &mut Text, &mut Callout)>,
+    time: Res<Time>,
+) {
+    callouts.for_each_mut(|(mut transform, mut text, mut callout)| {
+        callout.timer -= time.delta_seconds();
+        transform.translation.y += 80. * time.delta_seconds();
+        text.sections[0].style.color.set_a(callout.timer.max(0.0));
+    });
+}
+
+fn spawn_main_manu(
+    mut cmd: Commands,
+    ui: Res<UiAssets>,
+    assets: Res<PlayerAssets>,
+    wallet: Res<Wallet>,
+    settings: Res<Settings>,
+) {
+    let button = |parent: &mut ChildBuilder, label: &str, button: MenuButton, size: Size| {
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        size,
+                        margin: UiRect::all(Val::Px(10.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    ..default()
+                },
+                button,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font: ui.font.clone(),
+                        font_size: 50.0,
+                        color: Color::BLACK,
+                    },
+                ));
+            });
+    };
+
     cmd.spawn((
-        ButtonBundle {
+        NodeBundle {
             style: Style {
-                size: Size::new(Val::Px(350.0), Val::Px(85.0)),
-                margin: UiRect::all(Val::Auto),
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
                 ..default()
             },
-            background_color: Color::WHITE.into(),
             ..default()
         },
         UI,
     ))
     .with_children(|parent| {
-        parent.spawn(TextBundle::from_section(
-            "Start Game",
+        for (label, menu_button) in [
+            ("Start Game", MenuButton::Start),
+            ("Settings", MenuButton::Settings),
+            ("Shop", MenuButton::Shop),
+        ] {
+            button(
+                parent,
+                label,
+                menu_button,
+                Size::new(Val::Px(350.0), Val::Px(85.0)),
+            );
+        }
+        let arrow = Size::new(Val::Px(60.0), Val::Px(85.0));
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                button(parent, "<", MenuButton::PreviousDifficulty, arrow);
+                parent.spawn((
+                    TextBundle::from_section(
+                        settings.difficulty.label(),
+                        TextStyle {
+                            font: ui.font.clone(),
+                            font_size: 40.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_text_alignment(TextAlignment::CENTER)
+                    .with_style(Style {
+                        size: Size::new(Val::Px(210.0), Val::Auto),
+                        ..default()
+                    }),
+                    DifficultyName,
+                ));
+                button(parent, ">", MenuButton::NextDifficulty, arrow);
+            });
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                button(parent, "<", MenuButton::PreviousMode, arrow);
+                parent.spawn((
+                    TextBundle::from_section(
+                        settings.mode.label(),
+                        TextStyle {
+                            font: ui.font.clone(),
+                            font_size: 40.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_text_alignment(TextAlignment::CENTER)
+                    .with_style(Style {
+                        size: Size::new(Val::Px(210.0), Val::Auto),
+                        ..default()
+                    }),
+                    ModeName,
+                ));
+                button(parent, ">", MenuButton::NextMode, arrow);
+            });
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                button(parent, "<", MenuButton::PreviousSkin, arrow);
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(210.0), Val::Auto),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((
+                            ImageBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(120.0), Val::Px(120.0)),
+                                    ..default()
+                                },
+                                image: assets.preview().into(),
+                                background_color: wallet.tint().into(),
+                                ..default()
+                            },
+                            SkinPreview,
+                        ));
+                        parent.spawn((
+                            TextBundle::from_section(
+                                assets.skin.clone(),
+                                TextStyle {
+                                    font: ui.font.clone(),
+                                    font_size: 30.0,
+                                    color: Color::WHITE,
+                                },
+                            ),
+                            SkinName,
+                        ));
+                    });
+                button(parent, ">", MenuButton::NextSkin, arrow);
+            });
+    });
+}
+
+fn main_menu(
+    mut state: ResMut<State<GameState>>,
+    mut settings: ResMut<Settings>,
+    skins: Res<SkinDefinitions>,
+    mut butt: MenuButtons,
+) {
+    butt.for_each_mut(|(int, mut col, button)| match *int {
+        Interaction::Clicked => {
+            *col = Color::DARK_GRAY.into();
+            let next = match button {
+                MenuButton::Start => GameState::Playing,
+                MenuButton::Settings => GameState::Settings,
+                MenuButton::Shop => GameState::Shop,
+                MenuButton::PreviousSkin | MenuButton::NextSkin => {
+                    let steps = if matches!(button, MenuButton::NextSkin) {
+                        1
+                    } else {
+                        -1
+                    };
+                    let skin = skins.step(settings.skin.as_deref(), steps);
+                    settings.skin = Some(skin.name.clone());
+                    return;
+                }
+                MenuButton::PreviousDifficulty => {
+                    settings.difficulty = settings.difficulty.step(-1);
+                    return;
+                }
+                MenuButton::NextDifficulty => {
+                    settings.difficulty = settings.difficulty.step(1);
+                    return;
+                }
+                MenuButton::PreviousMode => {
+                    settings.mode = settings.mode.step(-1);
+                    return;
+                }
+                MenuButton::NextMode => {
+                    settings.mode = settings.mode.step(1);
+                    return;
+                }
+            };
+            if let Err(e) = state.set(next) {
+                println!("Error: {e}");
+            }
+        }
+        Interaction::Hovered => *col = Color::GRAY.into(),
+        Interaction::None => *col = Color::WHITE.into(),
+    })
+}
+
+fn update_difficulty_picker(
+    settings: Res<Settings>,
+    mut name: Query<&mut Text, With<DifficultyName>>,
+) {
+    name.for_each_mut(|mut text| text.sections[0].value = settings.difficulty.label().to_string());
+}
+
+fn update_mode_picker(settings: Res<Settings>, mut name: Query<&mut Text, With<ModeName>>) {
+    name.for_each_mut(|mut text| text.sections[0].value = settings.mode.label().to_string());
+}
+
+fn update_skin_picker(
+    assets: Res<PlayerAssets>,
+    mut name: Query<&mut Text, With<SkinName>>,
+    mut preview: Query<&mut UiImage, With<SkinPreview>>,
+) {
+    if !assets.is_changed() {
+        return;
+    }
+    name.for_each_mut(|mut text| text.sections[0].value = assets.skin.clone());
+    preview.for_each_mut(|mut image| *image = assets.preview().into());
+}
+
+fn spawn_settings_menu(mut cmd: Commands, ui: Res<UiAssets>) {
+    let text = |value: &str| {
+        TextBundle::from_section(
+            value,
             TextStyle {
                 font: ui.font.clone(),
-                font_size: 50.0,
                 color: Color::BLACK,
+                font_size: 30.0,
             },
+        )
+    };
+    let row = || NodeBundle {
+        style: Style {
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::Center,
+            margin: UiRect::all(Val::Px(5.0)),
+            ..default()
+        },
+        ..default()
+    };
+    let button = |parent: &mut ChildBuilder, label: &str, button: SettingsButton| {
+        parent
+            .spawn((
+                ButtonBundle {
+                    background_color: Color::GRAY.into(),
+                    style: Style {
+                        padding: UiRect::all(Val::Px(5.0)),
+                        margin: UiRect::horizontal(Val::Px(5.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                button,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        color: Color::WHITE,
+                        font: ui.font.clone(),
+                        font_size: 30.0,
+                    },
+                ));
+            });
+    };
+
+    cmd.spawn((
+        UI,
+        NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(60.0), Val::Percent(80.0)),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Percent(10.0),
+                    left: Val::Percent(20.0),
+                    ..default()
+                },
+                padding: UiRect::all(Val::Percent(3.0)),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::WHITE.into(),
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn(
+            TextBundle::from_section(
+                "Settings",
+                TextStyle {
+                    font: ui.font.clone(),
+                    color: Color::BLACK,
+                    font_size: 40.0,
+                },
+            )
+            .with_style(Style {
+                align_self: AlignSelf::Center,
+                ..default()
+            }),
+        );
+        for setting in Setting::ALL {
+            parent.spawn(row()).with_children(|parent| {
+                parent.spawn(text(setting.label()));
+                parent.spawn(row()).with_children(|parent| {
+                    button(parent, "<", SettingsButton::Decrease(setting));
+                    parent.spawn((text(""), SettingText(setting)));
+                    button(parent, ">", SettingsButton::Increase(setting));
+                });
+            });
+        }
+        for action in Action::ALL {
+            parent.spawn(row()).with_children(|parent| {
+                parent.spawn(text(&format!("{action:?}")));
+                parent.spawn(row()).with_children(|parent| {
+                    parent.spawn((text(""), BindingText(action)));
+                    button(parent, "Rebind", SettingsButton::Rebind(action));
+                });
+            });
+        }
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_grow: 1.0,
+                ..default()
+            },
+            ..default()
+        });
+        parent.spawn(row()).with_children(|parent| {
+            button(parent, "Back", SettingsButton::Back);
+        });
+    });
+}
+
+fn settings_menu(
+    mut state: ResMut<State<GameState>>,
+    mut settings: ResMut<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+    mut actions: ResMut<Input<Action>>,
+    mut butt: SettingsButtons,
+) {
+    butt.for_each_mut(|(int, mut col, button)| match *int {
+        Interaction::Clicked => {
+            *col = Color::DARK_GRAY.into();
+            match *button {
+                SettingsButton::Decrease(setting) => setting.adjust(&mut settings, -1.0),
+                SettingsButton::Increase(setting) => setting.adjust(&mut settings, 1.0),
+                SettingsButton::Rebind(action) => rebinding.0 = Some(action),
+                SettingsButton::Back => {
+                    if let Err(e) = state.set(GameState::MainMenu) {
+                        println!("Error: {e}");
+                    }
+                }
+            }
+        }
+        Interaction::Hovered => *col = Color::DARK_GRAY.into(),
+        Interaction::None => *col = Color::GRAY.into(),
+    });
+    if rebinding.0.is_none() && actions.clear_just_pressed(Action::Back) {
+        if let Err(e) = state.set(GameState::MainMenu) {
+            println!("Error: {e}");
+        }
+    }
+}
+
+/// Binds the next pressed button to the action picked for rebinding
+fn capture_binding(
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<InputBindings>,
+    mut actions: ResMut<Input<Action>>,
+    devices: Devices,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+    let Some(binding) = devices.any_just_pressed() else {
+        return;
+    };
+    bindings.bind(action, binding);
+    rebinding.0 = None;
+    // the button was meant for binding, it shouldn't also leave the screen
+    actions.clear_just_pressed(Action::Back);
+}
+
+fn update_settings_text(
+    settings: Res<Settings>,
+    bindings: Res<InputBindings>,
+    rebinding: Res<Rebinding>,
+    mut values: Query<(&mut Text, &SettingText), Without<BindingText>>,
+    mut binds: Query<(&mut Text, &BindingText), Without<SettingText>>,
+) {
+    values.for_each_mut(|(mut text, setting)| {
+        text.sections[0].value = setting.0.text(&settings);
+    });
+    binds.for_each_mut(|(mut text, action)| {
+        text.sections[0].value = if rebinding.0 == Some(action.0) {
+            String::from("Press a button...")
+        } else {
+            let bound: Vec<String> = bindings
+                .get(action.0)
+                .iter()
+                .map(|b| b.to_string())
+                .collect();
+            bound.join(", ")
+        };
+    });
+}
+
+fn spawn_shop(mut cmd: Commands, ui: Res<UiAssets>) {
+    let text = |value: &str| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: ui.font.clone(),
+                color: Color::BLACK,
+                font_size: 30.0,
+            },
+        )
+    };
+    let row = || NodeBundle {
+        style: Style {
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::Center,
+            margin: UiRect::all(Val::Px(5.0)),
+            ..default()
+        },
+        ..default()
+    };
+    let button = |parent: &mut ChildBuilder, label: &str, button: ShopButton| {
+        parent
+            .spawn((
+                ButtonBundle {
+                    background_color: Color::GRAY.into(),
+                    style: Style {
+                        padding: UiRect::all(Val::Px(5.0)),
+                        margin: UiRect::horizontal(Val::Px(5.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                button,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        color: Color::WHITE,
+                        font: ui.font.clone(),
+                        font_size: 30.0,
+                    },
+                ));
+            });
+    };
+
+    cmd.spawn((
+        UI,
+        NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(60.0), Val::Percent(80.0)),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Percent(10.0),
+                    left: Val::Percent(20.0),
+                    ..default()
+                },
+                padding: UiRect::all(Val::Percent(3.0)),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::WHITE.into(),
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn(
+            TextBundle::from_section(
+                "Shop",
+                TextStyle {
+                    font: ui.font.clone(),
+                    color: Color::BLACK,
+                    font_size: 40.0,
+                },
+            )
+            .with_style(Style {
+                align_self: AlignSelf::Center,
+                ..default()
+            }),
+        );
+        parent.spawn((
+            text("").with_style(Style {
+                align_self: AlignSelf::Center,
+                ..default()
+            }),
+            CoinBalance,
         ));
+        for (index, item) in SHOP_ITEMS.iter().enumerate() {
+            parent.spawn(row()).with_children(|parent| {
+                parent.spawn(text(item.name));
+                parent.spawn(row()).with_children(|parent| {
+                    parent.spawn((text(""), ShopItemText(index)));
+                    button(parent, "Get", ShopButton::Item(index));
+                });
+            });
+        }
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_grow: 1.0,
+                ..default()
+            },
+            ..default()
+        });
+        parent.spawn(row()).with_children(|parent| {
+            button(parent, "Back", ShopButton::Back);
+        });
     });
 }
 
-fn main_menu(
+fn shop_menu(
     mut state: ResMut<State<GameState>>,
-    mut butt: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
+    mut wallet: ResMut<Wallet>,
+    mut actions: ResMut<Input<Action>>,
+    mut butt: ShopButtons,
 ) {
-    butt.for_each_mut(|(int, mut col)| match *int {
+    butt.for_each_mut(|(int, mut col, button)| match *int {
         Interaction::Clicked => {
             *col = Color::DARK_GRAY.into();
-            state.set(GameState::Playing).unwrap();
+            match *button {
+                ShopButton::Item(index) => {
+                    let item = &SHOP_ITEMS[index];
+                    if wallet.buy(item) {
+                        wallet.equipped = Some(item.name.to_string());
+                        wallet.save();
+                    }
+                }
+                ShopButton::Back => {
+                    if let Err(e) = state.set(GameState::MainMenu) {
+                        println!("Error: {e}");
+                    }
+                }
+            }
         }
-        Interaction::Hovered => *col = Color::GRAY.into(),
-        Interaction::None => *col = Color::WHITE.into(),
-    })
+        Interaction::Hovered => *col = Color::DARK_GRAY.into(),
+        Interaction::None => *col = Color::GRAY.into(),
+    });
+    if actions.clear_just_pressed(Action::Back) {
+        if let Err(e) = state.set(GameState::MainMenu) {
+            println!("Error: {e}");
+        }
+    }
+}
+
+fn update_shop_text(
+    wallet: Res<Wallet>,
+    mut balance: Query<&mut Text, (With<CoinBalance>, Without<ShopItemText>)>,
+    mut items: Query<(&mut Text, &ShopItemText), Without<CoinBalance>>,
+) {
+    balance.for_each_mut(|mut text| {
+        text.sections[0].value = format!("Coins: {}", wallet.coins);
+    });
+    items.for_each_mut(|(mut text, index)| {
+        let item = &SHOP_ITEMS[index.0];
+        text.sections[0].value = if wallet.is_equipped(item) {
+            String::from("Equipped")
+        } else if wallet.owns(item) {
+            String::from("Owned")
+        } else {
+            item.price.to_string()
+        };
+    });
 }
 
 fn clean_ui(mut cmd: Commands, butt: Query<Entity, With<UI>>) {
@@ -164,15 +1366,122 @@ fn clean_ui(mut cmd: Commands, butt: Query<Entity, With<UI>>) {
     });
 }
 
-fn spawn_end_score(mut cmd: Commands, ui: Res<UiAssets>, score: Res<Score>) {
+fn spawn_pause_menu(mut cmd: Commands, ui: Res<UiAssets>) {
+    cmd.spawn((
+        PauseMenu,
+        NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn(
+            TextBundle::from_section(
+                "Paused",
+                TextStyle {
+                    font: ui.font.clone(),
+                    font_size: 80.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_style(Style {
+                margin: UiRect::all(Val::Px(20.0)),
+                ..default()
+            }),
+        );
+        [
+            ("Resume", PauseButton::Resume),
+            ("End Run", PauseButton::EndRun),
+        ]
+        .into_iter()
+        .for_each(|(label, button)| {
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(350.0), Val::Px(85.0)),
+                            margin: UiRect::all(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    button,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        label,
+                        TextStyle {
+                            font: ui.font.clone(),
+                            font_size: 50.0,
+                            color: Color::BLACK,
+                        },
+                    ));
+                });
+        });
+    });
+}
+
+fn pause_menu(
+    mut state: ResMut<State<GameState>>,
+    mut elapsed: ResMut<ElapsedTime>,
+    mut butt: PauseButtons,
+) {
+    butt.for_each_mut(|(int, mut col, button)| match *int {
+        Interaction::Clicked => {
+            *col = Color::DARK_GRAY.into();
+            match button {
+                PauseButton::Resume => {
+                    if let Err(e) = state.pop() {
+                        println!("Error: {e}");
+                    }
+                }
+                // the only way a Zen run ends
+                PauseButton::EndRun => {
+                    elapsed.finished = true;
+                    if let Err(e) = state.replace(GameState::End) {
+                        println!("Error: {e}");
+                    }
+                }
+            }
+        }
+        Interaction::Hovered => *col = Color::GRAY.into(),
+        Interaction::None => *col = Color::WHITE.into(),
+    })
+}
+
+fn clean_pause_menu(mut cmd: Commands, menu: Query<Entity, With<PauseMenu>>) {
+    menu.for_each(|x| {
+        cmd.entity(x).despawn_recursive();
+    });
+}
+
+fn spawn_end_score(
+    mut cmd: Commands,
+    ui: Res<UiAssets>,
+    score: Res<Score>,
+    high_scores: Res<HighScores>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+) {
     cmd.spawn((
         UI,
         NodeBundle {
             style: Style {
-                size: Size::new(Val::Percent(50.0), Val::Percent(50.0)),
+                size: Size::new(Val::Percent(50.0), Val::Percent(80.0)),
                 position_type: PositionType::Absolute,
                 position: UiRect {
-                    top: Val::Percent(25.0),
+                    top: Val::Percent(10.0),
                     left: Val::Percent(25.0),
                     ..default()
                 },
@@ -257,6 +1566,44 @@ fn spawn_end_score(mut cmd: Commands, ui: Res<UiAssets>, score: Res<Score>) {
                 ..default()
             }),
         );
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_grow: 20.0,
+                ..default()
+            },
+            ..default()
+        });
+        parent.spawn(
+            TextBundle::from_section(
+                format!("High Scores ({}, {})", difficulty.label(), mode.label()),
+                TextStyle {
+                    font: ui.font.clone(),
+                    color: Color::BLACK,
+                    font_size: 40.0,
+                },
+            )
+            .with_style(Style {
+                align_self: AlignSelf::Center,
+                ..default()
+            }),
+        );
+        let latest = high_scores.latest.as_ref();
+        high_scores
+            .table(*difficulty, *mode)
+            .enumerate()
+            .for_each(|(place, entry)| {
+                let highlight = latest.is_some_and(|x| x.1 == Some(place));
+                parent.spawn(high_score_line(
+                    &ui,
+                    &format!("{}.", place + 1),
+                    entry,
+                    highlight,
+                ));
+            });
+        // the finished run didn't make it to the table, it's shown under it instead
+        if let Some((entry, None)) = latest {
+            parent.spawn(high_score_line(&ui, "-", entry, true));
+        }
         parent.spawn(NodeBundle {
             style: Style {
                 flex_grow: 1000.0,
@@ -292,16 +1639,114 @@ fn spawn_end_score(mut cmd: Commands, ui: Res<UiAssets>, score: Res<Score>) {
     });
 }
 
-fn end_screen_menu(
-    mut state: ResMut<State<GameState>>,
-    mut butt: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
-) {
+fn high_score_line(ui: &UiAssets, place: &str, entry: &ScoreEntry, highlight: bool) -> TextBundle {
+    let minutes = entry.time as i32 / 60;
+    let seconds = entry.time as i32 % 60;
+    TextBundle::from_section(
+        format!(
+            "{} {} - combo {} - {}:{:02}",
+            place, entry.score, entry.max_combo, minutes, seconds
+        ),
+        TextStyle {
+            font: ui.font.clone(),
+            color: if highlight {
+                Color::ORANGE_RED
+            } else {
+                Color::BLACK
+            },
+            font_size: 25.0,
+        },
+    )
+    .with_style(Style {
+        align_self: AlignSelf::Center,
+        ..default()
+    })
+}
+
+fn end_screen_menu(mut state: ResMut<State<GameState>>, mut butt: ButtonInteractions) {
     butt.for_each_mut(|mut b| match b.0 {
         Interaction::Clicked => {
             *b.1 = Color::DARK_GRAY.into();
-            state.set(GameState::Playing).unwrap();
+            if let Err(e) = state.set(GameState::Playing) {
+                println!("Error: {e}");
+            }
         }
         Interaction::Hovered => *b.1 = Color::DARK_GRAY.into(),
         Interaction::None => *b.1 = Color::GRAY.into(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score_with_combo(combo: i32) -> Score {
+        Score {
+            current_combo: combo,
+            max_combo: combo,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn tiers_start_at_the_combo_thresholds() {
+        let tiers = [
+            (0, 1),
+            (9, 1),
+            (10, 2),
+            (24, 2),
+            (25, 3),
+            (49, 3),
+            (50, 4),
+            (99, 4),
+            (100, 5),
+            (1000, 5),
+        ];
+        for (combo, tier) in tiers {
+            assert_eq!(score_with_combo(combo).tier(), tier, "combo {combo}");
+        }
+    }
+
+    #[test]
+    fn bonus_grows_with_the_tier() {
+        let mut score = score_with_combo(9);
+        assert_eq!(score.add_to_score(1, true), (1, 0));
+        // the kill is scored with the tier the combo had before it
+        assert_eq!(score.add_to_score(1, true), (1, 1));
+        assert_eq!(score.score, 3);
+        assert_eq!(score.current_combo, 11);
+        assert_eq!(score.max_combo, 11);
+        assert_eq!(score.combo_timer, COMBO_TIME);
+
+        let mut score = Score {
+            multiplier: 3,
+            ..score_with_combo(50)
+        };
+        // chains and the multiplier raise the points, the bonus follows them
+        assert_eq!(score.add_to_score(2, true), (6, 18));
+        assert_eq!(score.score, 24);
+    }
+
+    #[test]
+    fn hardcore_has_no_combo_bonus() {
+        let mut score = score_with_combo(100);
+        assert_eq!(
+            score.add_to_score(2, GameMode::Hardcore.combo_bonus()),
+            (2, 0)
+        );
+        assert_eq!(score.score, 2);
+        assert_eq!(score.current_combo, 101);
+        assert!(GameMode::Endless.combo_bonus());
+    }
+
+    #[test]
+    fn reset_drops_the_combo_but_keeps_the_best() {
+        let mut score = score_with_combo(30);
+        score.add_to_score(1, true);
+        score.reset_combo();
+        assert_eq!(score.current_combo, 0);
+        assert_eq!(score.combo_timer, 0.0);
+        assert_eq!(score.max_combo, 31);
+        assert_eq!(score.tier(), 1);
+    }
+}